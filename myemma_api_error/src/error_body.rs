@@ -0,0 +1,45 @@
+use crate::ApiError;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+/// The representation of an `ApiError` that is returned to the user.
+/// Private information of the error is never included.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ApiErrorBody {
+    /// HTTP status code.
+    #[cfg_attr(feature = "utoipa", schema(example = 404))]
+    pub status: u16,
+    /// Description of the HTTP status, for example `Not Found`.
+    pub error: String,
+    /// Api error message code, `0` for private errors.
+    pub code: u16,
+    /// Error message that can be shown to the user.
+    pub message: String,
+    /// Unique error id, can be used to find the related logs.
+    pub id: String,
+}
+
+impl<C> From<&ApiError<C>> for ApiErrorBody
+where
+    C: Default + Debug + Clone + PartialEq,
+    u16: From<C>,
+{
+    fn from(api_error: &ApiError<C>) -> Self {
+        Self {
+            status: api_error.kind.into(),
+            error: api_error.kind.to_string(),
+            code: api_error.get_code_for_public_use(),
+            message: api_error.get_msg_for_public_use(),
+            id: api_error.get_unique_id(),
+        }
+    }
+}
+
+impl ApiErrorBody {
+    /// Serialize the body as JSON.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Error during serializing error body")
+    }
+}
@@ -1,7 +1,9 @@
 use backtrace::Backtrace;
 
-use crate::{ApiError, ApiErrorKind};
-use std::fmt::Debug;
+use crate::{ApiError, ApiErrorKind, ErrorReport, ServiceContext};
+use myemma_backtrace::BacktraceFilter;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
 
 pub trait ApiErrorManipulation<C>
 where
@@ -11,35 +13,69 @@ where
     u16: From<C>,
 {
     #[must_use]
+    #[track_caller]
     fn new<S: AsRef<str>>(msg: S, kind: ApiErrorKind, code: C) -> Self {
         Self::from(ApiError::<C>::new(msg, kind, code))
     }
 
     #[must_use]
+    #[track_caller]
     fn new_private<S: AsRef<str>>(msg: S) -> Self {
         Self::from(ApiError::<C>::new_private(msg))
     }
 
     #[must_use]
+    #[track_caller]
     fn new_unauthorized<S: AsRef<str>>(msg: S) -> Self {
         Self::from(ApiError::<C>::new_unauthorized(msg))
     }
 
     #[must_use]
+    #[track_caller]
     fn new_by_kind(kind: ApiErrorKind) -> Self {
         Self::from(ApiError::<C>::new_by_kind(kind))
     }
 
     #[must_use]
+    #[track_caller]
     fn new_by_code(code: C) -> Self {
         Self::from(ApiError::<C>::new_by_code(code))
     }
 
     #[must_use]
+    #[track_caller]
     fn new_internal_server_error() -> Self {
         Self::from(ApiError::<C>::new_internal_server_error())
     }
 
+    /// Add the error that caused this error to the cause chain.
+    #[must_use]
+    fn with_cause<E: Display>(self, cause: E) -> Self {
+        Self::from(ApiError::<C>::from(self).with_cause(cause))
+    }
+
+    /// Add the error and all its sources (`Error::source`) to the cause chain.
+    #[must_use]
+    fn with_error_chain<E: std::error::Error>(self, error: &E) -> Self {
+        Self::from(ApiError::<C>::from(self).with_error_chain(error))
+    }
+
+    /// Add extra information about this error, only used internally.
+    #[must_use]
+    fn with_metadata<K: Into<String>, V: Display>(self, key: K, value: V) -> Self {
+        Self::from(ApiError::<C>::from(self).with_metadata(key, value))
+    }
+
+    #[must_use]
+    fn get_causes(&self) -> Vec<String> {
+        self.as_ref().get_causes()
+    }
+
+    #[must_use]
+    fn get_metadata(&self) -> BTreeMap<String, String> {
+        self.as_ref().get_metadata()
+    }
+
     #[must_use]
     fn get_msg_for_internal_use(&self) -> String {
         self.as_ref().get_msg_for_internal_use()
@@ -96,6 +132,17 @@ where
         self.as_ref().display_error_for_internal_use()
     }
 
+    /// Create a full internal report of this error in the Error Reporting format.
+    #[must_use]
+    fn error_report<B: BacktraceFilter>(
+        &self,
+        service_context: &ServiceContext,
+        backtrace_count: u16,
+    ) -> ErrorReport {
+        self.as_ref()
+            .error_report::<B>(service_context, backtrace_count)
+    }
+
     /// Conditionally change internal error when code matched with `self`.
     #[must_use]
     fn transform_on_code(self, code: C, other: Self) -> Self {
@@ -0,0 +1,185 @@
+use crate::ApiError;
+use chrono::{DateTime, Utc};
+use myemma_backtrace::{BacktraceFilter, FilteredFrame};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+/// The `@type` that marks a log entry as an event for the Error Reporting service.
+/// More info see: https://cloud.google.com/error-reporting/docs/formatting-error-messages#@type
+const REPORTED_ERROR_EVENT_TYPE: &str =
+    "type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent";
+
+/// The service that reported the error, used by Error Reporting to group errors.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServiceContext {
+    /// Name of the service, for example `my-backend`.
+    pub service: String,
+    /// Version of the service, for example the release or git hash.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl ServiceContext {
+    pub fn new<S: Into<String>>(service: S, version: Option<String>) -> Self {
+        Self {
+            service: service.into(),
+            version,
+        }
+    }
+}
+
+/// Full internal report of an `ApiError`.
+///
+/// When serialized this is a `ReportedErrorEvent` compatible log entry.
+/// More info see: https://cloud.google.com/error-reporting/docs/formatting-error-messages
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorReport {
+    #[serde(rename = "@type")]
+    report_type: &'static str,
+    severity: &'static str,
+    pub event_time: DateTime<Utc>,
+    pub service_context: ServiceContext,
+    /// Message including the stack trace, so Error Reporting can group the errors.
+    /// The causes are only in `error`, so the stack trace can be parsed.
+    pub message: String,
+    pub context: ErrorContext,
+    /// All information about the error, only for internal use.
+    pub error: ErrorDetails,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorContext {
+    /// Location in the code where the error was created, the first frame of the backtrace
+    /// or where the `ApiError` was created when there are no frames.
+    /// Required by Error Reporting when no stack trace can be parsed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report_location: Option<ReportLocation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportLocation {
+    pub file_path: String,
+    pub line_number: u32,
+    pub function_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorDetails {
+    pub id: String,
+    pub kind: String,
+    pub code: u16,
+    pub message: String,
+    pub causes: Vec<String>,
+    pub metadata: BTreeMap<String, String>,
+    pub backtrace: Vec<ReportedFrame>,
+}
+
+/// A single frame of the filtered backtrace.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportedFrame {
+    pub function: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+}
+
+impl From<FilteredFrame> for ReportedFrame {
+    fn from(frame: FilteredFrame) -> Self {
+        Self {
+            function: frame.name,
+            file: frame
+                .filename
+                .map(|filename| filename.display().to_string()),
+            line: frame.lineno,
+            column: frame.colno,
+        }
+    }
+}
+
+impl ErrorReport {
+    /// Serialize the report as a single line log entry.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Error during serializing error report")
+    }
+}
+
+impl<C> ApiError<C>
+where
+    C: Default + Debug + Clone + PartialEq,
+    u16: From<C>,
+{
+    /// Create a full internal report of this error in the Error Reporting format.
+    /// The backtrace is resolved and filtered using `B`.
+    #[must_use]
+    pub fn error_report<B: BacktraceFilter>(
+        &self,
+        service_context: &ServiceContext,
+        backtrace_count: u16,
+    ) -> ErrorReport {
+        let backtrace: Vec<ReportedFrame> =
+            B::get_filtered_frames(&self.backtrace, backtrace_count)
+                .into_iter()
+                .map(ReportedFrame::from)
+                .collect();
+        // Without frames the message has no stack trace, then the location is required.
+        let report_location = match backtrace.first() {
+            Some(frame) => ReportLocation {
+                file_path: frame.file.clone().unwrap_or_default(),
+                line_number: frame.line.unwrap_or_default(),
+                function_name: frame.function.clone(),
+            },
+            None => ReportLocation {
+                file_path: self.location.file().to_owned(),
+                line_number: self.location.line(),
+                function_name: "unknown".to_owned(),
+            },
+        };
+
+        // Stack trace in the format that Error Reporting parses:
+        // https://cloud.google.com/error-reporting/docs/formatting-error-messages#log-text
+        let mut message = format!(
+            "ApiError: {} (ID: {}, Code: {}, Kind: {:?})",
+            self.msg,
+            self.unique_id,
+            self.get_code_for_internal_use(),
+            self.kind,
+        );
+        for frame in &backtrace {
+            message.push_str(&format!(
+                "\n    at {} ({}:{}:{})",
+                frame.function,
+                frame.file.as_deref().unwrap_or("unknown"),
+                frame.line.unwrap_or_default(),
+                frame.column.unwrap_or_default(),
+            ));
+        }
+
+        ErrorReport {
+            report_type: REPORTED_ERROR_EVENT_TYPE,
+            severity: "ERROR",
+            event_time: Utc::now(),
+            service_context: service_context.clone(),
+            message,
+            context: ErrorContext {
+                report_location: Some(report_location),
+            },
+            error: ErrorDetails {
+                id: self.unique_id.clone(),
+                kind: format!("{:?}", self.kind),
+                code: self.get_code_for_internal_use(),
+                message: self.msg.clone(),
+                causes: self.causes.clone(),
+                metadata: self.metadata.clone(),
+                backtrace,
+            },
+        }
+    }
+}
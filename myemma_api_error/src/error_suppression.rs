@@ -0,0 +1,235 @@
+use crate::ErrorFingerprint;
+use myemma_backtrace::BacktraceFilter;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+static ERROR_SUPPRESSION: OnceLock<ErrorSuppression> = OnceLock::new();
+
+/// How often a background thread checks for summaries that are due,
+/// for errors that stopped repeating.
+const MAX_SUMMARY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const MIN_SUMMARY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Only log the first occurrence of an error in full,
+/// repeated errors (with the same `ErrorFingerprint`) are summarized periodically.
+/// The summary is logged on the next repeat after the interval, or by a background thread
+/// shortly after the interval when the error is not repeated anymore.
+///
+/// This only affects the errors that are logged by this crate (and `ApiError::log_error`).
+/// Once enabled it can not be disabled again.
+pub fn enable_error_suppression<B: BacktraceFilter>(summary_interval: Duration) {
+    let suppression = ErrorSuppression {
+        filter: B::filter,
+        summary_interval,
+        seen: Mutex::new(HashMap::new()),
+    };
+    if ERROR_SUPPRESSION.set(suppression).is_err() {
+        log::warn!("Repeated error suppression was already enabled.");
+        return;
+    }
+    let check_interval =
+        summary_interval.clamp(MIN_SUMMARY_CHECK_INTERVAL, MAX_SUMMARY_CHECK_INTERVAL);
+    let summaries = std::thread::Builder::new()
+        .name("myemma-error-summary".to_owned())
+        .spawn(move || loop {
+            std::thread::sleep(check_interval);
+            if let Some(suppression) = get_error_suppression() {
+                for summary in suppression.due_summaries() {
+                    summary.log();
+                }
+            }
+        });
+    if let Err(err) = summaries {
+        log::warn!("Could not start the error summary thread: {}", err);
+    }
+}
+
+pub(crate) fn get_error_suppression() -> Option<&'static ErrorSuppression> {
+    ERROR_SUPPRESSION.get()
+}
+
+/// What should be logged for an occurrence of an error.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Occurrence {
+    /// First time this fingerprint was seen, log in full.
+    First,
+    /// Repeated error, do not log.
+    Suppressed,
+    /// Repeated error, log a summary of the repeats since the last summary.
+    Summary { count: u64, since: Duration },
+}
+
+#[derive(Debug)]
+struct SeenError {
+    /// Amount of times the error was seen since the last log.
+    count: u64,
+    last_logged: Instant,
+    /// Message and link of the last suppressed occurrence.
+    latest: String,
+}
+
+/// Summary of an error that was suppressed for a whole interval.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Summary {
+    fingerprint: ErrorFingerprint,
+    count: u64,
+    since: Duration,
+    latest: String,
+}
+
+impl Summary {
+    fn log(&self) {
+        log::error!(
+            "Error with fingerprint {} seen {} times in the last {}s, latest: {}",
+            self.fingerprint,
+            self.count,
+            self.since.as_secs(),
+            self.latest
+        );
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ErrorSuppression {
+    pub(crate) filter: fn(&backtrace::Symbol) -> bool,
+    summary_interval: Duration,
+    seen: Mutex<HashMap<ErrorFingerprint, SeenError>>,
+}
+
+impl ErrorSuppression {
+    /// Register an occurrence of an error and decide what should be logged.
+    /// `latest` is the message and link that is used in a later summary.
+    pub(crate) fn register(&self, fingerprint: ErrorFingerprint, latest: String) -> Occurrence {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        match seen.get_mut(&fingerprint) {
+            None => {
+                seen.insert(
+                    fingerprint,
+                    SeenError {
+                        count: 0,
+                        last_logged: now,
+                        latest,
+                    },
+                );
+                Occurrence::First
+            }
+            Some(seen_error) => {
+                seen_error.count += 1;
+                let since = now.duration_since(seen_error.last_logged);
+                if since < self.summary_interval {
+                    seen_error.latest = latest;
+                    return Occurrence::Suppressed;
+                }
+                let count = seen_error.count;
+                seen_error.count = 0;
+                seen_error.last_logged = now;
+                Occurrence::Summary { count, since }
+            }
+        }
+    }
+
+    /// Take the summaries of the errors that were suppressed for a whole interval,
+    /// so they are reported when the errors are not repeated anymore.
+    pub(crate) fn due_summaries(&self) -> Vec<Summary> {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        seen.iter_mut()
+            .filter(|(_, seen_error)| {
+                seen_error.count > 0
+                    && now.duration_since(seen_error.last_logged) >= self.summary_interval
+            })
+            .map(|(fingerprint, seen_error)| {
+                let summary = Summary {
+                    fingerprint: *fingerprint,
+                    count: seen_error.count,
+                    since: now.duration_since(seen_error.last_logged),
+                    latest: std::mem::take(&mut seen_error.latest),
+                };
+                seen_error.count = 0;
+                seen_error.last_logged = now;
+                summary
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_suppression(summary_interval: Duration) -> ErrorSuppression {
+        ErrorSuppression {
+            filter: |_| true,
+            summary_interval,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[test]
+    fn test_suppress_repeated_errors() {
+        let suppression = new_suppression(Duration::from_secs(3600));
+        let fingerprint = ErrorFingerprint::new(1, 500, "ServerError", ["main"]);
+        let other_fingerprint = ErrorFingerprint::new(2, 500, "ServerError", ["main"]);
+
+        assert_eq!(
+            Occurrence::First,
+            suppression.register(fingerprint, String::new())
+        );
+        assert_eq!(
+            Occurrence::Suppressed,
+            suppression.register(fingerprint, String::new())
+        );
+        assert_eq!(
+            Occurrence::Suppressed,
+            suppression.register(fingerprint, String::new())
+        );
+        assert_eq!(
+            Occurrence::First,
+            suppression.register(other_fingerprint, String::new())
+        );
+    }
+
+    #[test]
+    fn test_summarize_repeated_errors() {
+        let suppression = new_suppression(Duration::ZERO);
+        let fingerprint = ErrorFingerprint::new(1, 500, "ServerError", ["main"]);
+
+        assert_eq!(
+            Occurrence::First,
+            suppression.register(fingerprint, String::new())
+        );
+        assert!(matches!(
+            suppression.register(fingerprint, String::new()),
+            Occurrence::Summary { count: 1, .. }
+        ));
+        assert!(matches!(
+            suppression.register(fingerprint, String::new()),
+            Occurrence::Summary { count: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_summary_after_interval_without_repeats() {
+        let suppression = new_suppression(Duration::from_millis(20));
+        let fingerprint = ErrorFingerprint::new(1, 500, "ServerError", ["main"]);
+
+        assert_eq!(
+            Occurrence::First,
+            suppression.register(fingerprint, "first".to_owned())
+        );
+        assert_eq!(
+            Occurrence::Suppressed,
+            suppression.register(fingerprint, "second".to_owned())
+        );
+        assert!(suppression.due_summaries().is_empty());
+
+        std::thread::sleep(Duration::from_millis(30));
+        let summaries = suppression.due_summaries();
+        assert_eq!(1, summaries.len());
+        assert_eq!(1, summaries[0].count);
+        assert_eq!("second", summaries[0].latest);
+        assert!(suppression.due_summaries().is_empty());
+    }
+}
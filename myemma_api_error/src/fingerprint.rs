@@ -0,0 +1,36 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+
+/// Amount of filtered backtrace frames that are included in an `ErrorFingerprint`.
+pub const FINGERPRINT_FRAME_COUNT: u16 = 3;
+
+/// Identifies errors with the same code and kind that were created at the same place.
+/// Errors with an equal fingerprint are considered repeats of each other.
+///
+/// The fingerprint is only stable within builds of the same compiler version,
+/// so it should not be stored for a longer period of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorFingerprint(u64);
+
+impl ErrorFingerprint {
+    pub(crate) fn new<'a, I>(code: u16, kind_code: u16, kind: &str, frames: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut hasher = DefaultHasher::new();
+        code.hash(&mut hasher);
+        kind_code.hash(&mut hasher);
+        kind.hash(&mut hasher);
+        for frame in frames {
+            frame.hash(&mut hasher);
+        }
+        Self(hasher.finish())
+    }
+}
+
+impl Display for ErrorFingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
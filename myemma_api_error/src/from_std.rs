@@ -6,13 +6,15 @@ where
     C: Default + Debug + Clone + PartialEq,
     u16: From<C>,
 {
+    #[track_caller]
     fn from(err: std::num::ParseIntError) -> Self {
         let int_err = Self::new(
             "Could not parse int.",
             ApiErrorKind::BadRequest,
             C::default(),
-        );
-        log::error!("Could not parse int: {}. {}", err, int_err.log_link());
+        )
+        .with_cause(&err);
+        int_err.log_error(format_args!("Could not parse int: {}", err));
         int_err
     }
 }
@@ -22,13 +24,15 @@ where
     C: Default + Debug + Clone + PartialEq,
     u16: From<C>,
 {
+    #[track_caller]
     fn from(err: std::num::TryFromIntError) -> Self {
         let int_err = Self::new(
             "Failed to convert number.",
             ApiErrorKind::ServerError,
             C::default(),
-        );
-        log::error!("Failed to convert number: {}. {}", err, int_err.log_link());
+        )
+        .with_cause(err);
+        int_err.log_error(format_args!("Failed to convert number: {}", err));
         int_err
     }
 }
@@ -40,13 +44,14 @@ where
     C: Default + Debug + Clone + PartialEq,
     u16: From<C>,
 {
+    #[track_caller]
     fn from(err: std::convert::Infallible) -> Self {
         let int_err = Self::new(
             "This error can never happen.",
             ApiErrorKind::ServerError,
             C::default(),
         );
-        log::error!("Error can never happen: {}. {}", err, int_err.log_link());
+        int_err.log_error(format_args!("Error can never happen: {}", err));
         int_err
     }
 }
@@ -56,9 +61,11 @@ where
     C: Default + Debug + Clone + PartialEq,
     u16: From<C>,
 {
+    #[track_caller]
     fn from(err: std::io::Error) -> Self {
-        let int_err = Self::new("IO Error", ApiErrorKind::ServerError, C::default());
-        log::error!("IO error: {}. {}", err, int_err.log_link());
+        let int_err =
+            Self::new("IO Error", ApiErrorKind::ServerError, C::default()).with_cause(&err);
+        int_err.log_error(format_args!("IO error: {}", err));
         int_err
     }
 }
@@ -68,13 +75,15 @@ where
     C: Default + Debug + Clone + PartialEq,
     u16: From<C>,
 {
+    #[track_caller]
     fn from(err: core::str::Utf8Error) -> Self {
         let int_err = Self::new(
             format!("Utf8Error Error: {}", err),
             ApiErrorKind::BadRequest,
             C::default(),
-        );
-        log::error!("Utf8Error error: {}. {}", err, int_err.log_link());
+        )
+        .with_cause(err);
+        int_err.log_error(format_args!("Utf8Error error: {}", err));
         int_err
     }
 }
@@ -84,13 +93,15 @@ where
     C: Default + Debug + Clone + PartialEq,
     u16: From<C>,
 {
+    #[track_caller]
     fn from(err: std::string::FromUtf8Error) -> Self {
         let int_err = Self::new(
             format!("FromUtf8Error Error: {}", err),
             ApiErrorKind::BadRequest,
             C::default(),
-        );
-        log::error!("FromUtf8Error error: {}. {}", err, int_err.log_link());
+        )
+        .with_cause(&err);
+        int_err.log_error(format_args!("FromUtf8Error error: {}", err));
         int_err
     }
 }
@@ -100,14 +111,16 @@ where
     C: Default + Debug + Clone + PartialEq,
     u16: From<C>,
 {
+    #[track_caller]
     fn from(err: Box<dyn std::any::Any + Send>) -> Self {
-        let int_err = Self::new("Internal Error", ApiErrorKind::BadRequest, C::default());
+        let mut int_err = Self::new("Internal Error", ApiErrorKind::BadRequest, C::default());
         match err.downcast_ref::<String>() {
             Some(as_string) => {
-                log::error!("API Error (panic?): {}. {}", as_string, int_err.log_link());
+                int_err = int_err.with_cause(as_string);
+                int_err.log_error(format_args!("API Error (panic?): {}", as_string));
             }
             None => {
-                log::error!("API Error (panic?), unknown data. {}", int_err.log_link());
+                int_err.log_error(format_args!("API Error (panic?), unknown data"));
             }
         }
         int_err
@@ -0,0 +1,69 @@
+use crate::{ApiError, ApiErrorBody};
+use http::{header, Response, StatusCode};
+use std::fmt::Debug;
+
+impl<C> ApiError<C>
+where
+    C: Default + Debug + Clone + PartialEq,
+    u16: From<C>,
+{
+    /// Create the response that is returned to the user for this error.
+    /// The body is the JSON representation of `ApiErrorBody`.
+    #[must_use]
+    pub fn to_http_response(&self) -> Response<Vec<u8>> {
+        let status = StatusCode::from_u16(self.kind.into())
+            .expect("All `ApiErrorKind`s map to a valid status code");
+        Response::builder()
+            .status(status)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(ApiErrorBody::from(self).to_json().into_bytes())
+            .expect("Error during building error response")
+    }
+}
+
+impl<C> From<ApiError<C>> for Response<Vec<u8>>
+where
+    C: Default + Debug + Clone + PartialEq,
+    u16: From<C>,
+{
+    fn from(api_error: ApiError<C>) -> Self {
+        api_error.to_http_response()
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<C> axum_core::response::IntoResponse for ApiError<C>
+where
+    C: Default + Debug + Clone + PartialEq,
+    u16: From<C>,
+{
+    fn into_response(self) -> axum_core::response::Response {
+        let (parts, body) = self.to_http_response().into_parts();
+        Response::from_parts(parts, axum_core::body::Body::from(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ApiError, ApiErrorBody, ApiErrorKind};
+
+    #[test]
+    fn test_http_response() {
+        let error = ApiError::<u16>::new("Could not find user.", ApiErrorKind::NotFound, 12);
+        let response = error.to_http_response();
+
+        assert_eq!(404, response.status().as_u16());
+        assert_eq!("application/json", response.headers()["content-type"]);
+        let body: ApiErrorBody = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(
+            ApiErrorBody {
+                status: 404,
+                error: "Not Found".to_owned(),
+                code: 12,
+                message: "Could not find user.".to_owned(),
+                id: error.get_unique_id(),
+            },
+            body
+        );
+    }
+}
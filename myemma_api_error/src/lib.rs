@@ -1,14 +1,38 @@
 #![forbid(unsafe_code)]
 #![deny(clippy::all)]
 
+mod error_body;
 mod error_kind;
 mod error_manipulation;
+mod error_report;
+mod error_suppression;
+mod fingerprint;
 mod from_std;
+#[cfg(feature = "http")]
+mod http_response;
+#[cfg(feature = "utoipa")]
+mod openapi;
+#[cfg(feature = "rocket")]
+mod rocket_catchers;
+#[cfg(feature = "rocket")]
+mod rocket_response;
 
 use backtrace::Backtrace;
+pub use error_body::ApiErrorBody;
 pub use error_kind::ApiErrorKind;
 pub use error_manipulation::ApiErrorManipulation;
-use std::fmt::Debug;
+pub use error_report::{ErrorReport, ServiceContext};
+pub use error_suppression::enable_error_suppression;
+use error_suppression::{get_error_suppression, Occurrence};
+pub use fingerprint::{ErrorFingerprint, FINGERPRINT_FRAME_COUNT};
+use myemma_backtrace::BacktraceFilter;
+#[cfg(feature = "utoipa")]
+pub use openapi::{error_responses, DocumentedErrorCode};
+#[cfg(feature = "rocket")]
+pub use rocket_catchers::catchers;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+use std::panic::Location;
 
 /// Represents all errors that may occur in the application (server).
 /// These errors will not be returned to the user, but they can be converted.
@@ -25,6 +49,12 @@ pub struct ApiError<C> {
     unique_id: String,
     /// Backtrace
     backtrace: Backtrace,
+    /// Messages of the errors that caused this error, the direct cause first.
+    causes: Vec<String>,
+    /// Location in the code where the error was created.
+    location: &'static Location<'static>,
+    /// Extra information about the error for internal use.
+    metadata: BTreeMap<String, String>,
 }
 
 impl<C: PartialEq> PartialEq for ApiError<C> {
@@ -42,6 +72,7 @@ where
     u16: From<C>,
 {
     #[must_use]
+    #[track_caller]
     pub fn new<S: AsRef<str>>(msg: S, kind: ApiErrorKind, code: C) -> Self {
         let mut new_internal_error = Self {
             msg: msg.as_ref().to_owned(),
@@ -49,33 +80,42 @@ where
             code,
             unique_id: "".to_owned(),
             backtrace: Backtrace::new_unresolved(),
+            causes: Vec::new(),
+            metadata: BTreeMap::new(),
+            location: Location::caller(),
         };
         Self::create_new_issue_id(&mut new_internal_error);
         new_internal_error
     }
 
     #[must_use]
+    #[track_caller]
     pub fn new_private<S: AsRef<str>>(msg: S) -> Self {
-        log::error!("Private error {}", msg.as_ref());
-        Self::new(msg, ApiErrorKind::PrivateError, C::default())
+        let private_error = Self::new(msg, ApiErrorKind::PrivateError, C::default());
+        private_error.log_error(format_args!("Private error {}", private_error.msg));
+        private_error
     }
 
     #[must_use]
+    #[track_caller]
     pub fn new_unauthorized<S: AsRef<str>>(msg: S) -> Self {
         Self::new(msg, ApiErrorKind::Unauthorized, C::default())
     }
 
     #[must_use]
+    #[track_caller]
     pub fn new_by_kind(kind: ApiErrorKind) -> Self {
         Self::new("", kind, C::default())
     }
 
     #[must_use]
+    #[track_caller]
     pub fn new_by_code(code: C) -> Self {
         Self::new("", ApiErrorKind::ServerError, code)
     }
 
     #[must_use]
+    #[track_caller]
     pub fn new_internal_server_error() -> Self {
         Self::new(
             "The server had an internal error.",
@@ -91,6 +131,47 @@ where
         backtrace
     }
 
+    /// Add the error that caused this error to the cause chain.
+    #[must_use]
+    pub fn with_cause<E: Display>(mut self, cause: E) -> Self {
+        self.causes.push(cause.to_string());
+        self
+    }
+
+    /// Add the error and all its sources (`Error::source`) to the cause chain.
+    #[must_use]
+    pub fn with_error_chain<E: std::error::Error>(mut self, error: &E) -> Self {
+        let mut cause: Option<&dyn std::error::Error> = Some(error);
+        while let Some(error) = cause {
+            self.causes.push(error.to_string());
+            cause = error.source();
+        }
+        self
+    }
+
+    /// Add extra information about this error, only used internally.
+    #[must_use]
+    pub fn with_metadata<K: Into<String>, V: Display>(mut self, key: K, value: V) -> Self {
+        self.metadata.insert(key.into(), value.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn get_causes(&self) -> Vec<String> {
+        self.causes.clone()
+    }
+
+    /// Location in the code where the error was created.
+    #[must_use]
+    pub fn get_location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    #[must_use]
+    pub fn get_metadata(&self) -> BTreeMap<String, String> {
+        self.metadata.clone()
+    }
+
     #[must_use]
     pub fn get_msg_for_internal_use(&self) -> String {
         self.msg.clone()
@@ -162,6 +243,62 @@ where
         )
     }
 
+    /// Get the fingerprint of this error, based on the code, kind and the top filtered frames
+    /// of the backtrace. Repeats of the same error will have the same fingerprint.
+    #[must_use]
+    pub fn fingerprint<B: BacktraceFilter>(&self) -> ErrorFingerprint {
+        self.fingerprint_with(B::filter)
+    }
+
+    fn fingerprint_with(&self, filter: fn(&backtrace::Symbol) -> bool) -> ErrorFingerprint {
+        let frames =
+            myemma_backtrace::filtered_frames(&self.backtrace, filter, FINGERPRINT_FRAME_COUNT);
+        ErrorFingerprint::new(
+            self.get_code_for_internal_use(),
+            self.kind.into(),
+            &format!("{:?}", self.kind),
+            frames.iter().map(|frame| frame.name.as_str()),
+        )
+    }
+
+    /// Log the message at error level together with the link to this error.
+    ///
+    /// When repeated error suppression is enabled (see `enable_error_suppression`)
+    /// only the first occurrence of this error is logged in full,
+    /// repeats are logged as a periodic summary.
+    pub fn log_error(&self, message: std::fmt::Arguments) {
+        let suppression = match get_error_suppression() {
+            Some(suppression) => suppression,
+            None => {
+                log::error!("{}. {}", message, self.log_link());
+                return;
+            }
+        };
+        let fingerprint = self.fingerprint_with(suppression.filter);
+        let latest = format!("{}. {}", message, self.log_link());
+        match suppression.register(fingerprint, latest) {
+            Occurrence::First => {
+                log::error!(
+                    "{}. {} (Fingerprint: {})",
+                    message,
+                    self.log_link(),
+                    fingerprint
+                );
+            }
+            Occurrence::Suppressed => {}
+            Occurrence::Summary { count, since } => {
+                log::error!(
+                    "Error with fingerprint {} seen {} times in the last {}s, latest: {}. {}",
+                    fingerprint,
+                    count,
+                    since.as_secs(),
+                    message,
+                    self.log_link()
+                );
+            }
+        }
+    }
+
     /// Conditionally change internal error when code matched with `self`.
     #[must_use]
     pub fn transform_on_code(self, code: C, other: Self) -> Self {
@@ -285,12 +422,114 @@ mod tests {
                 kind: ApiErrorKind::PrivateError,
                 code: ApiErrorCodes::Default,
                 unique_id: error.get_unique_id(),
-                backtrace: Backtrace::new_unresolved()
+                backtrace: Backtrace::new_unresolved(),
+                causes: Vec::new(),
+                metadata: BTreeMap::new(),
+                location: error.get_location(),
             },
             error
         );
     }
 
+    struct TestBacktraceFilter;
+
+    impl BacktraceFilter for TestBacktraceFilter {
+        fn filter(symbol: &backtrace::Symbol) -> bool {
+            symbol
+                .name()
+                .map(|name| format!("{:#}", name).starts_with("myemma_api_error::tests::"))
+                .unwrap_or_default()
+        }
+    }
+
+    fn new_error_at_same_place(kind: ApiErrorKind) -> ApiError<ApiErrorCodes> {
+        ApiError::new("error message", kind, ApiErrorCodes::Default)
+    }
+
+    #[test]
+    fn test_error_fingerprint() {
+        let error = new_error_at_same_place(ApiErrorKind::ServerError);
+        let repeated_error = new_error_at_same_place(ApiErrorKind::ServerError);
+        let other_kind_error = new_error_at_same_place(ApiErrorKind::NotFound);
+        assert_ne!(error.get_unique_id(), repeated_error.get_unique_id());
+        assert_eq!(
+            error.fingerprint::<TestBacktraceFilter>(),
+            repeated_error.fingerprint::<TestBacktraceFilter>()
+        );
+        assert_ne!(
+            error.fingerprint::<TestBacktraceFilter>(),
+            other_kind_error.fingerprint::<TestBacktraceFilter>()
+        );
+    }
+
+    #[test]
+    fn test_error_report() {
+        let error = new_error_at_same_place(ApiErrorKind::NotFound)
+            .with_cause("file not found")
+            .with_metadata("user_id", 5);
+        let service_context = ServiceContext::new("backend", Some("1.0.0".to_owned()));
+        let report = error.error_report::<TestBacktraceFilter>(&service_context, 4);
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+
+        assert_eq!(
+            "type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent",
+            json["@type"]
+        );
+        assert_eq!("backend", json["serviceContext"]["service"]);
+        assert_eq!(error.get_unique_id(), json["error"]["id"]);
+        assert_eq!("NotFound", json["error"]["kind"]);
+        assert_eq!("file not found", json["error"]["causes"][0]);
+        assert_eq!("5", json["error"]["metadata"]["user_id"]);
+        assert!(report.message.starts_with("ApiError: error message"));
+        assert!(!report.message.contains("file not found"));
+    }
+
+    struct NoBacktraceFilter;
+
+    impl BacktraceFilter for NoBacktraceFilter {
+        fn filter(_symbol: &backtrace::Symbol) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_report_location_without_backtrace() {
+        let line = line!() + 1;
+        let error = ApiError::<ApiErrorCodes>::new_by_kind(ApiErrorKind::NotFound);
+        let report =
+            error.error_report::<NoBacktraceFilter>(&ServiceContext::new("backend", None), 4);
+        let location = report.context.report_location.unwrap();
+        assert_eq!(file!(), location.file_path);
+        assert_eq!(line, location.line_number);
+    }
+
+    #[derive(Debug)]
+    struct ConfigError(std::io::Error);
+
+    impl Display for ConfigError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "could not read config")
+        }
+    }
+
+    impl std::error::Error for ConfigError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn test_error_chain() {
+        let config_error = ConfigError(std::io::Error::other("disk full"));
+        let error = ApiError::<ApiErrorCodes>::new_internal_server_error()
+            .with_cause("retry failed")
+            .with_error_chain(&config_error);
+        assert_eq!(
+            vec!["retry failed", "could not read config", "disk full"],
+            error.get_causes()
+        );
+    }
+
     #[test]
     fn test_error_display() {
         let error = ApiError::<ApiErrorCodes>::new_private("error message");
@@ -0,0 +1,120 @@
+use crate::{ApiErrorBody, ApiErrorKind};
+use std::collections::BTreeMap;
+use utoipa::openapi::{ContentBuilder, Ref, ResponseBuilder, Responses, ResponsesBuilder};
+
+/// Document the possible codes of an error code type in the OpenAPI schema.
+pub trait DocumentedErrorCode: Sized + Into<u16> {
+    /// All codes that can be returned to the user, together with a short description.
+    fn documented_codes() -> Vec<(Self, &'static str)>;
+}
+
+/// Create the OpenAPI responses for all `kinds` of errors a route can return.
+///
+/// Every response refers to the `ApiErrorBody` schema, so it should be registered as component:
+/// `#[openapi(components(schemas(ApiErrorBody)))]`.
+/// Kinds with the same HTTP status are combined in one response.
+#[must_use]
+pub fn error_responses<C: DocumentedErrorCode>(kinds: &[ApiErrorKind]) -> Responses {
+    let mut kinds_by_status: BTreeMap<u16, Vec<ApiErrorKind>> = BTreeMap::new();
+    for kind in kinds {
+        let status_kinds = kinds_by_status.entry(u16::from(*kind)).or_default();
+        if !status_kinds.contains(kind) {
+            status_kinds.push(*kind);
+        }
+    }
+
+    let codes: Vec<(u16, &'static str)> = C::documented_codes()
+        .into_iter()
+        .map(|(code, description)| (code.into(), description))
+        .collect();
+    let code_descriptions = codes
+        .iter()
+        .map(|(code, description)| format!("- `{}`: {}", code, description))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let mut responses = ResponsesBuilder::new();
+    for (status, status_kinds) in kinds_by_status {
+        let only_private = status_kinds
+            .iter()
+            .all(|kind| *kind == ApiErrorKind::PrivateError);
+        let description = if only_private || codes.is_empty() {
+            status_kinds[0].to_string()
+        } else {
+            format!(
+                "{}\n\nPossible codes:\n{}",
+                status_kinds[0], code_descriptions
+            )
+        };
+        let example = ApiErrorBody {
+            status,
+            error: status_kinds[0].to_string(),
+            code: if only_private {
+                0
+            } else {
+                codes.first().map(|(code, _)| *code).unwrap_or_default()
+            },
+            message: if only_private {
+                "Internal server error".to_owned()
+            } else {
+                status_kinds[0].to_string()
+            },
+            id: "Y2xCsa8dUMQWGcd2NHDm".to_owned(),
+        };
+        let content = ContentBuilder::new()
+            .schema(Some(Ref::from_schema_name("ApiErrorBody")))
+            .example(Some(
+                serde_json::to_value(example).expect("Error during serializing example"),
+            ))
+            .build();
+        responses = responses.response(
+            status.to_string(),
+            ResponseBuilder::new()
+                .description(description)
+                .content("application/json", content)
+                .build(),
+        );
+    }
+    responses.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utoipa::openapi::RefOr;
+
+    struct UserErrorCode(u16);
+
+    impl From<UserErrorCode> for u16 {
+        fn from(code: UserErrorCode) -> Self {
+            code.0
+        }
+    }
+
+    impl DocumentedErrorCode for UserErrorCode {
+        fn documented_codes() -> Vec<(Self, &'static str)> {
+            vec![(UserErrorCode(12), "User does not exist.")]
+        }
+    }
+
+    #[test]
+    fn test_error_responses() {
+        let responses = error_responses::<UserErrorCode>(&[
+            ApiErrorKind::NotFound,
+            ApiErrorKind::ServerError,
+            ApiErrorKind::PrivateError,
+        ]);
+
+        assert_eq!(
+            vec!["404", "500"],
+            responses.responses.keys().collect::<Vec<_>>()
+        );
+        match &responses.responses["404"] {
+            RefOr::T(response) => {
+                assert!(response.description.contains("`12`: User does not exist."));
+                assert!(response.content.contains_key("application/json"));
+            }
+            RefOr::Ref(_) => panic!("Expected inline response"),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{ApiError, ApiErrorBody, ApiErrorKind};
+use rocket::catcher::{self, Catcher, Handler};
+use rocket::http::{ContentType, Status};
+use rocket::response::{Responder, Response};
+use rocket::Request;
+use std::fmt::Debug;
+use std::io::Cursor;
+use std::marker::PhantomData;
+
+/// All statuses that have an `ApiErrorKind` and that will get a catcher.
+const CAUGHT_STATUSES: [u16; 6] = [400, 401, 403, 404, 422, 500];
+
+/// Catchers for the errors that Rocket returns itself (no matching route, failing guard, panic).
+/// These respond with the same body as an `ApiError` returned from a handler.
+/// A default catcher responds to all other statuses (405, 413, 503, ...) with the same body
+/// and the original status.
+///
+/// Register them with: `rocket.register("/", myemma_api_error::catchers::<ApiErrorCode>())`.
+#[must_use]
+pub fn catchers<C>() -> Vec<Catcher>
+where
+    C: Default + Debug + Clone + PartialEq + Send + Sync + 'static,
+    u16: From<C>,
+{
+    CAUGHT_STATUSES
+        .into_iter()
+        .map(Some)
+        .chain([None])
+        .map(|status| Catcher::new(status, ApiErrorCatcher::<C> { _code: PhantomData }))
+        .collect()
+}
+
+struct ApiErrorCatcher<C> {
+    _code: PhantomData<fn() -> C>,
+}
+
+impl<C> Clone for ApiErrorCatcher<C> {
+    fn clone(&self) -> Self {
+        Self { _code: PhantomData }
+    }
+}
+
+#[rocket::async_trait]
+impl<C> Handler for ApiErrorCatcher<C>
+where
+    C: Default + Debug + Clone + PartialEq + Send + Sync + 'static,
+    u16: From<C>,
+{
+    async fn handle<'r>(&self, status: Status, req: &'r Request<'_>) -> catcher::Result<'r> {
+        let kind = match ApiErrorKind::from(status.code) {
+            kind if <u16 as From<ApiErrorKind>>::from(kind) == status.code => kind,
+            _ if status.code >= 500 => ApiErrorKind::ServerError,
+            _ => ApiErrorKind::BadRequest,
+        };
+        let api_error = ApiError::<C>::new(status.reason_lossy(), kind, C::default());
+        if kind.is_server_error() {
+            api_error.log_error(format_args!(
+                "Request `{} {}` failed with status {}",
+                req.method(),
+                req.uri(),
+                status
+            ));
+        } else {
+            log::info!(
+                "Request `{} {}` failed with status {}. {}",
+                req.method(),
+                req.uri(),
+                status,
+                api_error.log_link()
+            );
+        }
+        if <u16 as From<ApiErrorKind>>::from(kind) == status.code {
+            return api_error.respond_to(req);
+        }
+        // Statuses without an `ApiErrorKind` keep their own status.
+        let body = ApiErrorBody {
+            status: status.code,
+            error: status.reason_lossy().to_owned(),
+            ..ApiErrorBody::from(&api_error)
+        }
+        .to_json();
+        Response::build()
+            .status(status)
+            .header(ContentType::JSON)
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ApiErrorBody;
+    use rocket::http::Status;
+    use rocket::local::blocking::Client;
+
+    #[test]
+    fn test_not_found_catcher() {
+        let rocket = rocket::build().register("/", super::catchers::<u16>());
+        let client = Client::tracked(rocket).unwrap();
+        let response = client.get("/does-not-exist").dispatch();
+
+        assert_eq!(Status::NotFound, response.status());
+        let body: ApiErrorBody = response.into_json().unwrap();
+        assert_eq!(404, body.status);
+        assert_eq!("Not Found", body.message);
+        assert_eq!(0, body.code);
+    }
+
+    #[rocket::get("/maintenance")]
+    fn maintenance() -> Status {
+        Status::ServiceUnavailable
+    }
+
+    #[test]
+    fn test_default_catcher() {
+        let rocket = rocket::build()
+            .mount("/", rocket::routes![maintenance])
+            .register("/", super::catchers::<u16>());
+        let client = Client::tracked(rocket).unwrap();
+        let response = client.get("/maintenance").dispatch();
+
+        assert_eq!(Status::ServiceUnavailable, response.status());
+        let body: ApiErrorBody = response.into_json().unwrap();
+        assert_eq!(503, body.status);
+        assert_eq!("Service Unavailable", body.error);
+        assert_eq!("Service Unavailable", body.message);
+    }
+}
@@ -0,0 +1,21 @@
+use crate::{ApiError, ApiErrorBody};
+use rocket::http::{ContentType, Status};
+use rocket::response::{self, Responder, Response};
+use rocket::Request;
+use std::fmt::Debug;
+use std::io::Cursor;
+
+impl<'r, C> Responder<'r, 'static> for ApiError<C>
+where
+    C: Default + Debug + Clone + PartialEq,
+    u16: From<C>,
+{
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let body = ApiErrorBody::from(&self).to_json();
+        Response::build()
+            .status(Status::new(self.kind.into()))
+            .header(ContentType::JSON)
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
@@ -4,11 +4,60 @@
 // Reexport crate
 pub use backtrace;
 
+use std::path::PathBuf;
+
+/// A resolved frame of a backtrace that passed a `BacktraceFilter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilteredFrame {
+    /// Demangled function name, without the hash suffix.
+    pub name: String,
+    pub filename: Option<PathBuf>,
+    pub lineno: Option<u32>,
+    pub colno: Option<u32>,
+}
+
+/// Resolve the frames of a captured (possibly unresolved) backtrace
+/// and return the first `amount` frames for which `filter` returns `true`.
+pub fn filtered_frames<F>(
+    backtrace: &backtrace::Backtrace,
+    filter: F,
+    amount: u16,
+) -> Vec<FilteredFrame>
+where
+    F: Fn(&backtrace::Symbol) -> bool,
+{
+    let mut frames = vec![];
+    for frame in backtrace.frames() {
+        if frames.len() >= usize::from(amount) {
+            break;
+        }
+        backtrace::resolve(frame.ip(), |symbol| {
+            if frames.len() >= usize::from(amount) || !filter(symbol) {
+                return;
+            }
+            if let Some(name) = symbol.name() {
+                frames.push(FilteredFrame {
+                    name: format!("{:#}", name),
+                    filename: symbol.filename().map(|filename| filename.to_path_buf()),
+                    lineno: symbol.lineno(),
+                    colno: symbol.colno(),
+                });
+            }
+        });
+    }
+    frames
+}
+
 pub trait BacktraceFilter {
     /// Filter out all frame symbols that you do or don't want in the backtrace list.
     /// Only the items that return `true` will be kept.
     fn filter(symbol: &backtrace::Symbol) -> bool;
 
+    /// Get the first `amount` frames of a captured backtrace that pass the filter.
+    fn get_filtered_frames(backtrace: &backtrace::Backtrace, amount: u16) -> Vec<FilteredFrame> {
+        filtered_frames(backtrace, Self::filter, amount)
+    }
+
     /// Get info about the last stack trace
     fn get_backtrace_info(mut amount: u16) -> String {
         let mut bt_info = vec![];
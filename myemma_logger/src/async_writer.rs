@@ -0,0 +1,392 @@
+use crate::{FormatOptions, LogEntry, LogSink};
+use log::Level;
+use std::collections::VecDeque;
+use std::io;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+
+/// What to do with a new line when the queue of the `AsyncWriter` is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Wait until there is room in the queue.
+    #[default]
+    Block,
+    /// Drop the new line.
+    DropNewest,
+    /// Drop the oldest `Debug` or `Trace` line in the queue to make room.
+    /// When there are none the new line is dropped.
+    DropDebugFirst,
+}
+
+#[derive(Debug, Clone)]
+pub struct AsyncWriterConfig {
+    /// Maximum amount of lines that are waiting to be written.
+    pub capacity: usize,
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl Default for AsyncWriterConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 8192,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+/// Writes the lines to a sink on a background thread, so logging does not wait on IO.
+///
+/// Clones share the same queue and background thread,
+/// the thread writes the remaining lines and stops when the last clone is dropped.
+#[derive(Clone)]
+pub struct AsyncWriter {
+    shared: Arc<Shared>,
+    _thread: Arc<WriterThread>,
+}
+
+/// Stops the writer thread when dropped.
+struct WriterThread {
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for WriterThread {
+    fn drop(&mut self) {
+        self.shared.lock_queue().shutdown = true;
+        self.shared.line_added.notify_all();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+struct Shared {
+    config: AsyncWriterConfig,
+    sink: LogSink,
+    queue: Mutex<Queue>,
+    /// Notified when a line is added to the queue.
+    line_added: Condvar,
+    /// Notified when the writer thread finished writing lines.
+    lines_written: Condvar,
+    dropped: AtomicU64,
+}
+
+/// A formatted line, with the entry for sinks that send the entry fields as well.
+#[derive(Debug)]
+struct QueuedLine {
+    level: Level,
+    line: String,
+    entry: Option<Box<(LogEntry, FormatOptions)>>,
+}
+
+#[derive(Default)]
+struct Queue {
+    lines: VecDeque<QueuedLine>,
+    /// The writer thread is writing lines that are no longer in `lines`.
+    writing: bool,
+    /// The writer thread stops when all lines are written.
+    shutdown: bool,
+}
+
+/// Result of trying to add a line to a full or non full queue.
+#[derive(Debug)]
+enum Push {
+    Added,
+    /// A line was dropped (the new line or an old one).
+    Dropped,
+    /// The queue is full, wait and try again with the returned line.
+    Full(QueuedLine),
+}
+
+impl Queue {
+    fn push(&mut self, config: &AsyncWriterConfig, line: QueuedLine) -> Push {
+        if self.lines.len() < config.capacity {
+            self.lines.push_back(line);
+            return Push::Added;
+        }
+        match config.overflow_policy {
+            OverflowPolicy::Block => Push::Full(line),
+            OverflowPolicy::DropNewest => Push::Dropped,
+            OverflowPolicy::DropDebugFirst => {
+                let debug_line = self
+                    .lines
+                    .iter()
+                    .position(|queued| queued.level >= Level::Debug);
+                if let Some(index) = debug_line {
+                    self.lines.remove(index);
+                    self.lines.push_back(line);
+                }
+                Push::Dropped
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for AsyncWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncWriter")
+            .field("config", &self.shared.config)
+            .field("dropped", &self.dropped_messages())
+            .finish()
+    }
+}
+
+impl AsyncWriter {
+    /// Start the background thread that writes all lines to `sink`,
+    /// fails when the thread can not be started.
+    pub fn new(sink: LogSink, config: AsyncWriterConfig) -> io::Result<Self> {
+        let shared = Arc::new(Shared {
+            config,
+            sink,
+            queue: Mutex::new(Queue::default()),
+            line_added: Condvar::new(),
+            lines_written: Condvar::new(),
+            dropped: AtomicU64::new(0),
+        });
+        let thread_shared = shared.clone();
+        let handle = std::thread::Builder::new()
+            .name("myemma-logger".to_owned())
+            .spawn(move || thread_shared.write_lines())?;
+        Ok(Self {
+            _thread: Arc::new(WriterThread {
+                shared: shared.clone(),
+                handle: Some(handle),
+            }),
+            shared,
+        })
+    }
+
+    /// Add the line to the queue, what happens when the queue is full depends on the
+    /// `OverflowPolicy`.
+    pub fn write_line(&self, level: Level, line: &str) {
+        self.push(QueuedLine {
+            level,
+            line: line.to_owned(),
+            entry: None,
+        });
+    }
+
+    /// Add the line to the queue, with the entry when the sink sends the entry fields.
+    pub(crate) fn write_entry(&self, entry: &LogEntry, line: &str, options: &FormatOptions) {
+        self.push(QueuedLine {
+            level: entry.level,
+            line: line.to_owned(),
+            entry: self
+                .shared
+                .sink
+                .takes_entries()
+                .then(|| Box::new((entry.clone(), options.clone()))),
+        });
+    }
+
+    fn push(&self, mut line: QueuedLine) {
+        let mut queue = self.shared.lock_queue();
+        loop {
+            // A full queue gives the line back, so it can be added after waiting.
+            match queue.push(&self.shared.config, line) {
+                Push::Added => break,
+                Push::Dropped => {
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    break;
+                }
+                Push::Full(returned) => {
+                    line = returned;
+                    queue = self
+                        .shared
+                        .lines_written
+                        .wait(queue)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+            }
+        }
+        self.shared.line_added.notify_one();
+    }
+
+    /// Wait until all lines in the queue are written.
+    pub fn flush(&self) {
+        let mut queue = self.shared.lock_queue();
+        while !queue.lines.is_empty() || queue.writing {
+            queue = self
+                .shared
+                .lines_written
+                .wait(queue)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// The sink the lines are written to.
+    pub fn sink(&self) -> &LogSink {
+        &self.shared.sink
+    }
+
+    /// Amount of lines that were dropped because the queue was full.
+    pub fn dropped_messages(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Shared {
+    fn lock_queue(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_lines(&self) {
+        loop {
+            let lines = {
+                let mut queue = self.lock_queue();
+                while queue.lines.is_empty() && !queue.shutdown {
+                    queue = self
+                        .line_added
+                        .wait(queue)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+                if queue.lines.is_empty() {
+                    return;
+                }
+                queue.writing = true;
+                std::mem::take(&mut queue.lines)
+            };
+            // Let waiting loggers continue while the lines are written.
+            self.lines_written.notify_all();
+            // A panicking sink loses the lines, but must not block `flush` and full queues.
+            let _ = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                for queued in lines {
+                    match queued.entry {
+                        Some(entry) => self.sink.write_entry(&entry.0, &queued.line, &entry.1),
+                        None => self.sink.write_line(queued.level, &queued.line),
+                    }
+                }
+                self.sink.flush();
+            }));
+            self.lock_queue().writing = false;
+            self.lines_written.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemorySink;
+
+    fn queued(level: Level, line: &str) -> QueuedLine {
+        QueuedLine {
+            level,
+            line: line.to_owned(),
+            entry: None,
+        }
+    }
+
+    fn full_queue(config: &AsyncWriterConfig) -> Queue {
+        let mut queue = Queue::default();
+        assert!(matches!(
+            queue.push(config, queued(Level::Info, "info")),
+            Push::Added
+        ));
+        assert!(matches!(
+            queue.push(config, queued(Level::Debug, "debug")),
+            Push::Added
+        ));
+        queue
+    }
+
+    #[test]
+    fn test_overflow_policy() {
+        let mut config = AsyncWriterConfig {
+            capacity: 2,
+            overflow_policy: OverflowPolicy::Block,
+        };
+        assert!(matches!(
+            full_queue(&config).push(&config, queued(Level::Error, "error")),
+            Push::Full(_)
+        ));
+
+        config.overflow_policy = OverflowPolicy::DropNewest;
+        let mut queue = full_queue(&config);
+        assert!(matches!(
+            queue.push(&config, queued(Level::Error, "error")),
+            Push::Dropped
+        ));
+        assert_eq!(vec!["info", "debug"], lines(&queue));
+
+        config.overflow_policy = OverflowPolicy::DropDebugFirst;
+        let mut queue = full_queue(&config);
+        assert!(matches!(
+            queue.push(&config, queued(Level::Error, "error")),
+            Push::Dropped
+        ));
+        assert_eq!(vec!["info", "error"], lines(&queue));
+        assert!(matches!(
+            queue.push(&config, queued(Level::Warn, "warn")),
+            Push::Dropped
+        ));
+        assert_eq!(vec!["info", "error"], lines(&queue));
+    }
+
+    fn lines(queue: &Queue) -> Vec<&str> {
+        queue
+            .lines
+            .iter()
+            .map(|queued| queued.line.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_flush_writes_all_lines() {
+        let memory = MemorySink::default();
+        let writer = AsyncWriter::new(
+            LogSink::Memory(memory.clone()),
+            AsyncWriterConfig::default(),
+        )
+        .unwrap();
+        for index in 0..100 {
+            writer.write_line(Level::Info, &index.to_string());
+        }
+        writer.flush();
+        assert_eq!(100, memory.lines().len());
+        assert_eq!(0, writer.dropped_messages());
+    }
+
+    #[test]
+    fn test_stop_thread_on_last_drop() {
+        let memory = MemorySink::default();
+        let writer = AsyncWriter::new(
+            LogSink::Memory(memory.clone()),
+            AsyncWriterConfig::default(),
+        )
+        .unwrap();
+        let shared = Arc::downgrade(&writer.shared);
+        writer.clone().write_line(Level::Info, "last");
+        drop(writer);
+        // The thread wrote the remaining line and released the shared state.
+        assert_eq!(vec!["last"], memory.lines());
+        assert!(shared.upgrade().is_none());
+    }
+
+    struct PanickingWriter;
+
+    impl io::Write for PanickingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            panic!("Sink failed");
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_flush_after_sink_panics() {
+        let writer = AsyncWriter::new(
+            LogSink::writer(PanickingWriter),
+            AsyncWriterConfig::default(),
+        )
+        .unwrap();
+        writer.write_line(Level::Info, "first");
+        writer.flush();
+        writer.write_line(Level::Info, "second");
+        writer.flush();
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{DirectiveError, DirectiveFilter};
+use log::Level;
+use std::str::FromStr;
+
+/// Directives of the default `BacktracePolicy`: only errors and warnings.
+pub const DEFAULT_BACKTRACE_DIRECTIVES: &str = "warn";
+
+/// Decides for which levels and targets a backtrace is captured, the same for all formats.
+///
+/// Uses the directives of a `DirectiveFilter`, for example `warn,services::billing=error`
+/// captures backtraces for errors and warnings, but only for errors of `services::billing`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BacktracePolicy {
+    directives: DirectiveFilter,
+}
+
+impl BacktracePolicy {
+    pub fn new(directives: DirectiveFilter) -> Self {
+        Self { directives }
+    }
+
+    /// Never capture a backtrace.
+    pub fn never() -> Self {
+        Self::new("off".parse().expect("Directive is valid"))
+    }
+
+    /// Whether a backtrace is captured for a message of `level` and `target`.
+    pub fn captures(&self, level: Level, target: &str) -> bool {
+        level <= self.directives.level_for(target)
+    }
+}
+
+impl Default for BacktracePolicy {
+    fn default() -> Self {
+        DEFAULT_BACKTRACE_DIRECTIVES
+            .parse()
+            .expect("Default directives are valid")
+    }
+}
+
+impl FromStr for BacktracePolicy {
+    type Err = DirectiveError;
+
+    fn from_str(directives: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(directives.parse()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backtrace_policy() {
+        let policy: BacktracePolicy = "warn,services::billing=error,rocket=off".parse().unwrap();
+        assert!(policy.captures(Level::Warn, "main"));
+        assert!(!policy.captures(Level::Info, "main"));
+        assert!(policy.captures(Level::Error, "services::billing::invoice"));
+        assert!(!policy.captures(Level::Warn, "services::billing::invoice"));
+        assert!(!policy.captures(Level::Error, "rocket::server"));
+        assert!(!BacktracePolicy::never().captures(Level::Error, "main"));
+    }
+}
@@ -0,0 +1,231 @@
+use crate::LogFilter;
+use log::{LevelFilter, Metadata};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+
+/// The error of the last `DirectiveFilter::default` with invalid directives,
+/// it is logged once the logger is installed.
+static DEFAULT_ERROR: Mutex<Option<DirectiveError>> = Mutex::new(None);
+
+/// Directives that are used when the environment variable is not set.
+pub const DEFAULT_DIRECTIVES: &str = "info";
+
+/// `LogFilter` configured with `RUST_LOG` style directives,
+/// for example: `info,rocket=warn,services::billing=trace`.
+///
+/// A directive without target sets the default level, when it is missing all targets without
+/// a directive are turned off. A target matches itself and all its sub modules,
+/// the directive with the longest matching target is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectiveFilter {
+    default_level: LevelFilter,
+    /// Sorted by the length of the target, longest first.
+    directives: Vec<(String, LevelFilter)>,
+}
+
+/// A directive could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectiveError {
+    pub directive: String,
+}
+
+impl Display for DirectiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid log directive: `{}`", self.directive)
+    }
+}
+
+impl std::error::Error for DirectiveError {}
+
+impl DirectiveFilter {
+    /// Read the directives from the environment variable `var`,
+    /// `DEFAULT_DIRECTIVES` are used when the variable is not set.
+    pub fn from_env(var: &str) -> Result<Self, DirectiveError> {
+        match std::env::var(var) {
+            Ok(directives) => directives.parse(),
+            Err(_) => DEFAULT_DIRECTIVES.parse(),
+        }
+    }
+
+    /// Replace invalid directives by `DEFAULT_DIRECTIVES`,
+    /// the error is logged as a warning when the logger is installed.
+    fn or_default(directives: Result<Self, DirectiveError>) -> Self {
+        directives.unwrap_or_else(|err| {
+            *DEFAULT_ERROR.lock().unwrap_or_else(PoisonError::into_inner) = Some(err);
+            DEFAULT_DIRECTIVES
+                .parse()
+                .expect("Default directives are valid")
+        })
+    }
+
+    /// The most verbose level that is enabled for any target.
+    /// Use it for `log::set_max_level`, so disabled messages are skipped early.
+    pub fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default_level, Ord::max)
+    }
+
+    /// The most verbose level that is enabled for `target`.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .find(|(directive_target, _)| {
+                target
+                    .strip_prefix(directive_target.as_str())
+                    .map(|rest| rest.is_empty() || rest.starts_with("::"))
+                    .unwrap_or_default()
+            })
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default_level)
+    }
+}
+
+/// Print the directives in the same format as they are parsed.
+impl Display for DirectiveFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut directives = vec![];
+        if self.default_level != LevelFilter::Off {
+            directives.push(self.default_level.to_string().to_lowercase());
+        }
+        for (target, level) in &self.directives {
+            directives.push(format!("{}={}", target, level.to_string().to_lowercase()));
+        }
+        write!(f, "{}", directives.join(","))
+    }
+}
+
+impl FromStr for DirectiveFilter {
+    type Err = DirectiveError;
+
+    fn from_str(directives: &str) -> Result<Self, Self::Err> {
+        let mut filter = DirectiveFilter {
+            default_level: LevelFilter::Off,
+            directives: Vec::new(),
+        };
+        for directive in directives.split(',').map(str::trim) {
+            if directive.is_empty() {
+                continue;
+            }
+            let error = || DirectiveError {
+                directive: directive.to_owned(),
+            };
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    let target = target.trim();
+                    let level = LevelFilter::from_str(level.trim()).map_err(|_| error())?;
+                    if target.is_empty() {
+                        return Err(error());
+                    }
+                    filter.directives.retain(|(existing, _)| existing != target);
+                    filter.directives.push((target.to_owned(), level));
+                }
+                None => match LevelFilter::from_str(directive) {
+                    Ok(level) => filter.default_level = level,
+                    // Only a target enables all levels for that target.
+                    Err(_) => {
+                        filter
+                            .directives
+                            .retain(|(existing, _)| existing != directive);
+                        filter
+                            .directives
+                            .push((directive.to_owned(), LevelFilter::Trace));
+                    }
+                },
+            }
+        }
+        filter
+            .directives
+            .sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        Ok(filter)
+    }
+}
+
+/// Uses the `RUST_LOG` environment variable, invalid directives are replaced by
+/// `DEFAULT_DIRECTIVES` and logged as a warning when the logger is installed.
+/// Use `DirectiveFilter::from_env` to handle the error yourself.
+impl Default for DirectiveFilter {
+    fn default() -> Self {
+        Self::or_default(Self::from_env("RUST_LOG"))
+    }
+}
+
+/// Log the error of invalid `RUST_LOG` directives, only the first time.
+pub(crate) fn log_default_error() {
+    let error = DEFAULT_ERROR
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some(error) = error {
+        log::warn!("{}, using `{}` instead.", error, DEFAULT_DIRECTIVES);
+    }
+}
+
+impl LogFilter for DirectiveFilter {
+    fn filter(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        DirectiveFilter::max_level(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_for_target() {
+        let filter: DirectiveFilter = "info,rocket=warn,services::billing=trace,hyper"
+            .parse()
+            .unwrap();
+
+        assert_eq!(LevelFilter::Info, filter.level_for("main"));
+        assert_eq!(LevelFilter::Warn, filter.level_for("rocket"));
+        assert_eq!(LevelFilter::Warn, filter.level_for("rocket::server"));
+        assert_eq!(LevelFilter::Info, filter.level_for("rocket_codegen"));
+        assert_eq!(LevelFilter::Info, filter.level_for("services::users"));
+        assert_eq!(
+            LevelFilter::Trace,
+            filter.level_for("services::billing::invoice")
+        );
+        assert_eq!(LevelFilter::Trace, filter.level_for("hyper::proto"));
+        assert_eq!(LevelFilter::Trace, filter.max_level());
+    }
+
+    #[test]
+    fn test_without_default_level() {
+        let filter: DirectiveFilter = "rocket=warn".parse().unwrap();
+
+        assert_eq!(LevelFilter::Off, filter.level_for("main"));
+        assert_eq!(LevelFilter::Warn, filter.max_level());
+    }
+
+    #[test]
+    fn test_invalid_directive() {
+        assert_eq!(
+            Err(DirectiveError {
+                directive: "rocket=loud".to_owned()
+            }),
+            "info,rocket=loud".parse::<DirectiveFilter>()
+        );
+    }
+
+    #[test]
+    fn test_invalid_directives_use_default_directives() {
+        let filter = DirectiveFilter::or_default("rocket=loud".parse());
+
+        assert_eq!(LevelFilter::Info, filter.max_level());
+        assert_eq!(
+            Some("rocket=loud".to_owned()),
+            DEFAULT_ERROR
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_ref()
+                .map(|error| error.directive.clone())
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use crate::LogEntry;
+use log::Level;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Which levels are checked for repeated messages and for how long.
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateConfig {
+    windows: HashMap<Level, Duration>,
+}
+
+impl DuplicateConfig {
+    /// Suppress repeated messages of all levels within `window`.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            windows: Level::iter().map(|level| (level, window)).collect(),
+        }
+    }
+
+    /// Change the window of a single level, `None` never suppresses messages of the level.
+    pub fn with_level_window(mut self, level: Level, window: Option<Duration>) -> Self {
+        match window {
+            Some(window) => self.windows.insert(level, window),
+            None => self.windows.remove(&level),
+        };
+        self
+    }
+}
+
+/// The last message of a target and level.
+#[derive(Debug)]
+struct LastMessage {
+    message: String,
+    /// Amount of times the message was suppressed.
+    repeated: u64,
+    since: Instant,
+}
+
+/// Collapses identical consecutive messages of the same target and level.
+///
+/// The first message is logged, repeats within the window are suppressed.
+/// When the window closes a single "previous message repeated N times" entry is logged.
+/// Windows are closed on the next different message, on every logged message after the
+/// window expired, by a background thread (started at the first repeat) when nothing
+/// is logged anymore, or when the logger is flushed.
+#[derive(Debug)]
+pub(crate) struct DuplicateFilter {
+    config: DuplicateConfig,
+    last_messages: Mutex<HashMap<(Level, String), LastMessage>>,
+    timer_started: OnceLock<()>,
+}
+
+/// What to do with a checked entry.
+#[derive(Debug)]
+pub(crate) struct Checked {
+    /// Log these summaries of closed windows first.
+    pub(crate) summaries: Vec<LogEntry>,
+    /// The entry is a repeat, do not log it.
+    pub(crate) suppress: bool,
+}
+
+impl DuplicateFilter {
+    pub(crate) fn new(config: DuplicateConfig) -> Self {
+        Self {
+            config,
+            last_messages: Mutex::new(HashMap::new()),
+            timer_started: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn check(&self, entry: &LogEntry) -> Checked {
+        let Some(window) = self.config.windows.get(&entry.level) else {
+            return Checked {
+                summaries: self.expired(),
+                suppress: false,
+            };
+        };
+        let now = Instant::now();
+        let mut last_messages = self
+            .last_messages
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut summaries = self.close_expired(&mut last_messages, now);
+        let key = (entry.level, entry.target.clone());
+        if let Some(last) = last_messages.get_mut(&key) {
+            if last.message == entry.message && now.duration_since(last.since) < *window {
+                last.repeated += 1;
+                return Checked {
+                    summaries,
+                    suppress: true,
+                };
+            }
+        }
+        let previous = last_messages.insert(
+            key,
+            LastMessage {
+                message: entry.message.clone(),
+                repeated: 0,
+                since: now,
+            },
+        );
+        summaries
+            .extend(previous.and_then(|last| summary(entry.level, &entry.target, last.repeated)));
+        Checked {
+            summaries,
+            suppress: false,
+        }
+    }
+
+    /// Close the windows that expired, returns the summaries of the suppressed messages.
+    pub(crate) fn expired(&self) -> Vec<LogEntry> {
+        let mut last_messages = self
+            .last_messages
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.close_expired(&mut last_messages, Instant::now())
+    }
+
+    fn close_expired(
+        &self,
+        last_messages: &mut HashMap<(Level, String), LastMessage>,
+        now: Instant,
+    ) -> Vec<LogEntry> {
+        let mut summaries = Vec::new();
+        last_messages.retain(|(level, target), last| {
+            let open = self
+                .config
+                .windows
+                .get(level)
+                .is_some_and(|window| now.duration_since(last.since) < *window);
+            if !open {
+                summaries.extend(summary(*level, target, last.repeated));
+            }
+            open
+        });
+        summaries
+    }
+
+    /// Close all windows, returns the summaries of the suppressed messages.
+    pub(crate) fn drain(&self) -> Vec<LogEntry> {
+        self.last_messages
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .drain()
+            .filter_map(|((level, target), last)| summary(level, &target, last.repeated))
+            .collect()
+    }
+
+    /// Start the thread that logs the summaries of expired windows with `write`,
+    /// only once. The thread stops when the filter is dropped.
+    pub(crate) fn start_timer<W>(self: &Arc<Self>, write: W)
+    where
+        W: Fn(&LogEntry) + Send + 'static,
+    {
+        self.timer_started.get_or_init(|| {
+            let interval = self
+                .config
+                .windows
+                .values()
+                .min()
+                .copied()
+                .unwrap_or(MAX_TIMER_INTERVAL)
+                .clamp(MIN_TIMER_INTERVAL, MAX_TIMER_INTERVAL);
+            let filter = Arc::downgrade(self);
+            let _ = std::thread::Builder::new()
+                .name("myemma-logger-duplicates".to_owned())
+                .spawn(move || loop {
+                    std::thread::sleep(interval);
+                    let Some(filter) = filter.upgrade() else {
+                        return;
+                    };
+                    for summary in filter.expired() {
+                        write(&summary);
+                    }
+                });
+        });
+    }
+}
+
+/// How often the background thread checks for expired windows.
+const MIN_TIMER_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_TIMER_INTERVAL: Duration = Duration::from_secs(1);
+
+fn summary(level: Level, target: &str, repeated: u64) -> Option<LogEntry> {
+    (repeated > 0).then(|| {
+        LogEntry::new(
+            level,
+            target,
+            format!(
+                "previous message repeated {} {}",
+                repeated,
+                if repeated == 1 { "time" } else { "times" }
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: Level, message: &str) -> LogEntry {
+        LogEntry::new(level, "services::billing", message.to_owned())
+    }
+
+    fn summary_messages(checked: Checked) -> Vec<String> {
+        assert!(!checked.suppress, "Entry is suppressed");
+        checked
+            .summaries
+            .into_iter()
+            .map(|summary| summary.message)
+            .collect()
+    }
+
+    fn is_suppressed(checked: Checked) -> bool {
+        checked.summaries.is_empty() && checked.suppress
+    }
+
+    #[test]
+    fn test_suppress_repeated_messages() {
+        let filter = DuplicateFilter::new(DuplicateConfig::new(Duration::from_secs(60)));
+
+        assert!(summary_messages(filter.check(&entry(Level::Error, "Failed"))).is_empty());
+        assert!(is_suppressed(filter.check(&entry(Level::Error, "Failed"))));
+        assert!(is_suppressed(filter.check(&entry(Level::Error, "Failed"))));
+        // Other levels are tracked separately.
+        assert!(summary_messages(filter.check(&entry(Level::Warn, "Failed"))).is_empty());
+        assert_eq!(
+            vec!["previous message repeated 2 times"],
+            summary_messages(filter.check(&entry(Level::Error, "Other")))
+        );
+        assert!(is_suppressed(filter.check(&entry(Level::Error, "Other"))));
+
+        let summaries = filter.drain();
+        assert_eq!(1, summaries.len());
+        assert_eq!("previous message repeated 1 time", summaries[0].message);
+    }
+
+    #[test]
+    fn test_window_per_level() {
+        let filter = DuplicateFilter::new(
+            DuplicateConfig::new(Duration::from_secs(60))
+                .with_level_window(Level::Info, None)
+                .with_level_window(Level::Debug, Some(Duration::ZERO)),
+        );
+        for level in [Level::Info, Level::Debug] {
+            assert!(summary_messages(filter.check(&entry(level, "Polling"))).is_empty());
+            assert!(summary_messages(filter.check(&entry(level, "Polling"))).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_close_expired_windows() {
+        let filter = DuplicateFilter::new(DuplicateConfig::new(Duration::from_millis(20)));
+        assert!(summary_messages(filter.check(&entry(Level::Error, "Failed"))).is_empty());
+        assert!(is_suppressed(filter.check(&entry(Level::Error, "Failed"))));
+        assert!(filter.expired().is_empty());
+
+        std::thread::sleep(Duration::from_millis(30));
+        // Any other message closes the expired window of the target.
+        let mut other = entry(Level::Info, "Started");
+        other.target = "main".to_owned();
+        assert_eq!(
+            vec!["previous message repeated 1 time"],
+            summary_messages(filter.check(&other))
+        );
+        assert!(filter.expired().is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::install::{install_logger, EntryLog, LoggerGuard};
+use crate::{LogEntry, LogFilter, Logger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use myemma_backtrace::BacktraceFilter;
+use std::sync::Arc;
+
+/// Dispatches every record to several loggers, for example Text on the console and
+/// Json in a file. Each logger keeps its own `LogFormat`, `LogFilter` and `LogSink`.
+pub struct FanOutLogger {
+    loggers: Vec<Box<dyn EntryLog>>,
+}
+
+impl std::fmt::Debug for FanOutLogger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FanOutLogger")
+            .field("loggers", &self.loggers.len())
+            .field("max_level", &self.max_level())
+            .finish()
+    }
+}
+
+impl Default for FanOutLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FanOutLogger {
+    pub fn new() -> Self {
+        Self {
+            loggers: Vec::new(),
+        }
+    }
+
+    /// Add a destination.
+    pub fn with_logger<B, L>(mut self, logger: Logger<B, L>) -> Self
+    where
+        B: BacktraceFilter + Sized + Send + Sync + 'static,
+        L: LogFilter + Sized + Send + Sync + Default + 'static,
+    {
+        self.loggers.push(Box::new(logger));
+        self
+    }
+
+    /// The most verbose level of all destinations, it follows the reloads of their filters.
+    pub fn max_level(&self) -> LevelFilter {
+        self.loggers
+            .iter()
+            .map(|logger| logger.max_level())
+            .fold(LevelFilter::Off, Ord::max)
+    }
+
+    /// Register as the global logger, see `Logger::try_install`.
+    pub fn try_install(self) -> Result<LoggerGuard, SetLoggerError> {
+        install_logger(Arc::new(self))
+    }
+
+    /// Same as `try_install`, but panics when a global logger was already registered.
+    pub fn install(self) -> LoggerGuard {
+        self.try_install()
+            .expect("Logger should be installed before any other logger")
+    }
+}
+
+impl Log for FanOutLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.loggers.iter().any(|logger| logger.enabled(metadata))
+    }
+
+    /// Every logger checks its own filter.
+    fn log(&self, record: &Record) {
+        for logger in &self.loggers {
+            logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        for logger in &self.loggers {
+            logger.flush();
+        }
+    }
+}
+
+/// Panics are logged by all destinations.
+impl EntryLog for FanOutLogger {
+    fn log_complete_entry(&self, entry: &LogEntry) {
+        for logger in &self.loggers {
+            logger.log_complete_entry(entry);
+        }
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        FanOutLogger::max_level(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::NoBacktraceFilter;
+    use crate::{LevelLogFilter, LogFilterExt, LogFormat, LogSink, MemorySink, ReloadableFilter};
+    use log::Level;
+
+    #[test]
+    fn test_log_to_all_destinations() {
+        let console = MemorySink::default();
+        let file = MemorySink::default();
+        let logger = FanOutLogger::new()
+            .with_logger(
+                Logger::<NoBacktraceFilter>::default()
+                    .with_format(LogFormat::Logfmt)
+                    .with_log_filter(LevelLogFilter(LevelFilter::Debug).boxed())
+                    .with_sink(LogSink::Memory(console.clone())),
+            )
+            .with_logger(
+                Logger::<NoBacktraceFilter>::default()
+                    .with_format(LogFormat::Json)
+                    .with_log_filter(LevelLogFilter(LevelFilter::Warn).boxed())
+                    .with_sink(LogSink::Memory(file.clone())),
+            );
+        assert_eq!(LevelFilter::Debug, logger.max_level());
+
+        for (level, message) in [(Level::Debug, "Cache miss"), (Level::Error, "Failed")] {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("{}", message))
+                    .level(level)
+                    .target("services::billing")
+                    .build(),
+            );
+        }
+        assert!(!logger.enabled(&Metadata::builder().level(Level::Trace).build()));
+
+        assert_eq!(2, console.lines().len());
+        assert!(console.lines()[0].contains(" msg=\"Cache miss\""));
+        let file_lines = file.lines();
+        assert_eq!(1, file_lines.len());
+        let entry: serde_json::Value = serde_json::from_str(&file_lines[0]).unwrap();
+        assert_eq!("Failed", entry["message"]);
+    }
+
+    #[test]
+    fn test_max_level_follows_reloads() {
+        let filter = ReloadableFilter::new("info".parse().unwrap());
+        let handle = filter.handle();
+        let logger = FanOutLogger::new()
+            .with_logger(
+                Logger::<NoBacktraceFilter, ReloadableFilter>::default().with_log_filter(filter),
+            )
+            .with_logger(
+                Logger::<NoBacktraceFilter>::default()
+                    .with_log_filter(LevelLogFilter(LevelFilter::Warn).boxed()),
+            );
+        assert_eq!(LevelFilter::Info, logger.max_level());
+
+        handle.reload("services=trace").unwrap();
+        assert_eq!(LevelFilter::Trace, logger.max_level());
+    }
+
+    #[test]
+    fn test_panic_to_all_destinations() {
+        let console = MemorySink::default();
+        let file = MemorySink::default();
+        let logger = FanOutLogger::new()
+            .with_logger(
+                Logger::<NoBacktraceFilter>::default()
+                    .with_format(LogFormat::Logfmt)
+                    .with_sink(LogSink::Memory(console.clone())),
+            )
+            .with_logger(
+                Logger::<NoBacktraceFilter>::default()
+                    // Panics are logged even when the filter would drop them.
+                    .with_log_filter(LevelLogFilter(LevelFilter::Off).boxed())
+                    .with_format(LogFormat::Json)
+                    .with_sink(LogSink::Memory(file.clone())),
+            );
+
+        logger.log_complete_entry(&LogEntry {
+            critical: true,
+            ..LogEntry::new(Level::Error, "panic", "Index out of bounds".to_owned())
+        });
+
+        assert_eq!(1, console.lines().len());
+        assert!(console.lines()[0].contains(" msg=\"Index out of bounds\""));
+        let entry: serde_json::Value = serde_json::from_str(&file.lines()[0]).unwrap();
+        assert_eq!("Index out of bounds", entry["message"]);
+    }
+}
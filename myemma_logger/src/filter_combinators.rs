@@ -0,0 +1,207 @@
+use crate::{DirectiveFilter, LogFilter};
+use log::{LevelFilter, Metadata};
+
+/// Combine filters into new filters, implemented for all `LogFilter`s.
+pub trait LogFilterExt: LogFilter + Sized {
+    /// Keep the messages that both filters keep.
+    fn and<F: LogFilter>(self, other: F) -> AndFilter<Self, F> {
+        AndFilter(self, other)
+    }
+
+    /// Keep the messages that any of the filters keeps.
+    fn or<F: LogFilter>(self, other: F) -> OrFilter<Self, F> {
+        OrFilter(self, other)
+    }
+
+    /// Keep the messages that this filter removes.
+    fn not(self) -> NotFilter<Self> {
+        NotFilter(self)
+    }
+
+    /// Erase the type, so filters can be assembled at runtime.
+    fn boxed(self) -> BoxedFilter
+    where
+        Self: Send + Sync + 'static,
+    {
+        BoxedFilter::new(self)
+    }
+}
+
+impl<F: LogFilter> LogFilterExt for F {}
+
+/// Filter chosen at runtime, for example from configuration.
+pub struct BoxedFilter(Box<dyn LogFilter + Send + Sync>);
+
+impl BoxedFilter {
+    pub fn new<F: LogFilter + Send + Sync + 'static>(filter: F) -> Self {
+        Self(Box::new(filter))
+    }
+}
+
+impl std::fmt::Debug for BoxedFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("BoxedFilter")
+            .field(&self.0.max_level())
+            .finish()
+    }
+}
+
+/// Uses the `RUST_LOG` environment variable, see `DirectiveFilter`.
+impl Default for BoxedFilter {
+    fn default() -> Self {
+        Self::new(DirectiveFilter::default())
+    }
+}
+
+impl LogFilter for BoxedFilter {
+    fn filter(&self, metadata: &Metadata) -> bool {
+        self.0.filter(metadata)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.0.max_level()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AndFilter<A, B>(A, B);
+
+impl<A: LogFilter, B: LogFilter> LogFilter for AndFilter<A, B> {
+    fn filter(&self, metadata: &Metadata) -> bool {
+        self.0.filter(metadata) && self.1.filter(metadata)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        Ord::min(self.0.max_level(), self.1.max_level())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OrFilter<A, B>(A, B);
+
+impl<A: LogFilter, B: LogFilter> LogFilter for OrFilter<A, B> {
+    fn filter(&self, metadata: &Metadata) -> bool {
+        self.0.filter(metadata) || self.1.filter(metadata)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        Ord::max(self.0.max_level(), self.1.max_level())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NotFilter<A>(A);
+
+impl<A: LogFilter> LogFilter for NotFilter<A> {
+    fn filter(&self, metadata: &Metadata) -> bool {
+        !self.0.filter(metadata)
+    }
+}
+
+/// Keep the messages up to a level.
+#[derive(Debug, Clone)]
+pub struct LevelLogFilter(pub LevelFilter);
+
+impl LogFilter for LevelLogFilter {
+    fn filter(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.0
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.0
+    }
+}
+
+/// Keep the messages of which the target starts with the prefix.
+#[derive(Debug, Clone)]
+pub struct TargetPrefixFilter(pub String);
+
+impl TargetPrefixFilter {
+    pub fn new(prefix: &str) -> Self {
+        Self(prefix.to_owned())
+    }
+}
+
+impl LogFilter for TargetPrefixFilter {
+    fn filter(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with(&self.0)
+    }
+}
+
+/// Keep the messages of a module and its sub modules.
+/// The target of a message is its module path, unless it was set explicitly.
+#[derive(Debug, Clone)]
+pub struct ModuleFilter(pub String);
+
+impl ModuleFilter {
+    pub fn new(module: &str) -> Self {
+        Self(module.to_owned())
+    }
+}
+
+impl LogFilter for ModuleFilter {
+    fn filter(&self, metadata: &Metadata) -> bool {
+        metadata
+            .target()
+            .strip_prefix(self.0.as_str())
+            .map(|rest| rest.is_empty() || rest.starts_with("::"))
+            .unwrap_or_default()
+    }
+}
+
+/// Keep the messages for which the closure returns `true`.
+#[derive(Clone)]
+pub struct FnFilter<F>(pub F);
+
+impl<F> std::fmt::Debug for FnFilter<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FnFilter")
+    }
+}
+
+impl<F: Fn(&Metadata) -> bool> LogFilter for FnFilter<F> {
+    fn filter(&self, metadata: &Metadata) -> bool {
+        (self.0)(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn keeps<F: LogFilter>(filter: &F, level: Level, target: &str) -> bool {
+        filter.filter(&Metadata::builder().level(level).target(target).build())
+    }
+
+    #[test]
+    fn test_combine_filters() {
+        let filter = LevelLogFilter(LevelFilter::Info)
+            .and(ModuleFilter::new("rocket").not())
+            .or(ModuleFilter::new("services::billing").and(LevelLogFilter(LevelFilter::Debug)))
+            .boxed();
+
+        assert!(keeps(&filter, Level::Info, "main"));
+        assert!(!keeps(&filter, Level::Debug, "main"));
+        assert!(!keeps(&filter, Level::Error, "rocket::server"));
+        assert!(keeps(&filter, Level::Info, "rocket_cors"));
+        assert!(keeps(&filter, Level::Debug, "services::billing::invoice"));
+        assert!(!keeps(&filter, Level::Trace, "services::billing"));
+        assert_eq!(LevelFilter::Debug, filter.max_level());
+    }
+
+    #[test]
+    fn test_max_level() {
+        let filter = LevelLogFilter(LevelFilter::Warn).and(TargetPrefixFilter::new("services"));
+        assert_eq!(LevelFilter::Warn, filter.max_level());
+        let filter = LevelLogFilter(LevelFilter::Warn).or(LevelLogFilter(LevelFilter::Debug));
+        assert_eq!(LevelFilter::Debug, filter.max_level());
+    }
+
+    #[test]
+    fn test_closure_filter() {
+        let filter = FnFilter(|metadata: &Metadata| metadata.target().contains("billing"));
+        assert!(keeps(&filter, Level::Trace, "services::billing"));
+        assert!(!keeps(&filter, Level::Error, "services::users"));
+    }
+}
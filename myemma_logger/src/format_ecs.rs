@@ -0,0 +1,81 @@
+use crate::log_fields::split_label_fields;
+use crate::{FormatOptions, LogEntry};
+use chrono::SecondsFormat;
+use serde_json::{json, Map, Value};
+
+/// Version of the Elastic Common Schema that is used.
+const ECS_VERSION: &str = "8.11.0";
+
+/// Format as Elastic Common Schema JSON: https://www.elastic.co/guide/en/ecs/current/
+pub(crate) fn format_ecs(entry: &LogEntry, options: &FormatOptions) -> String {
+    let (labels, fields) = split_label_fields(&entry.fields, &options.label_fields);
+    let mut log_entry = Map::new();
+    let mut insert = |key: &str, value: Value| {
+        if !value.is_null() {
+            log_entry.insert(key.to_owned(), value);
+        }
+    };
+    insert(
+        "@timestamp",
+        json!(entry.time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+    );
+    insert(
+        "log.level",
+        json!(if entry.critical {
+            "critical".to_owned()
+        } else {
+            entry.level.as_str().to_lowercase()
+        }),
+    );
+    insert("message", json!(entry.message));
+    insert("ecs.version", json!(ECS_VERSION));
+    insert("log.logger", json!(entry.target));
+    insert("log.origin.file.name", json!(entry.file));
+    insert("log.origin.file.line", json!(entry.line));
+    insert("log.origin.function", json!(entry.module_path));
+    insert("service.id", json!(options.id));
+    insert("service.name", json!(options.producer));
+    if let Some(trace_context) = &entry.trace_context {
+        insert("trace.id", json!(trace_context.trace_id));
+        insert("span.id", json!(trace_context.span_id));
+    }
+    if !entry.backtrace.is_empty() {
+        insert("error.stack_trace", json!(entry.backtrace.trim_start()));
+    }
+    if let Some(http_request) = &entry.http_request {
+        insert("http.request.method", http_request["requestMethod"].clone());
+        insert("url.original", http_request["requestUrl"].clone());
+        insert("http.response.status_code", http_request["status"].clone());
+        insert(
+            "http.response.body.bytes",
+            parse_number(&http_request["responseSize"]),
+        );
+        insert("user_agent.original", http_request["userAgent"].clone());
+        insert("client.ip", http_request["remoteIp"].clone());
+        insert("event.duration", latency_nanos(&http_request["latency"]));
+    }
+    if !labels.is_empty() {
+        insert("labels", json!(labels));
+    }
+    for (key, value) in fields {
+        log_entry.entry(key).or_insert(value);
+    }
+    Value::Object(log_entry).to_string()
+}
+
+fn parse_number(value: &Value) -> Value {
+    value
+        .as_str()
+        .and_then(|number| number.parse::<u64>().ok())
+        .map(Value::from)
+        .unwrap_or(Value::Null)
+}
+
+/// Convert a Google Cloud latency (`"0.012000s"`) to nanoseconds.
+pub(crate) fn latency_nanos(latency: &Value) -> Value {
+    latency
+        .as_str()
+        .and_then(|latency| latency.trim_end_matches('s').parse::<f64>().ok())
+        .map(|seconds| Value::from((seconds * 1_000_000_000.0).round() as u64))
+        .unwrap_or(Value::Null)
+}
@@ -0,0 +1,91 @@
+use crate::log_fields::split_label_fields;
+use crate::{FormatOptions, LogEntry};
+use google_cloud_logging::{GCLogSeverity, GCOperation, GCSourceLocation, GoogleCloudStructLog};
+use log::Level;
+use serde_json::Value;
+
+/// Field with the backtrace, Error Reporting reads the stack trace from it.
+/// More info see: https://cloud.google.com/error-reporting/docs/formatting-error-messages#log-text
+const STACK_TRACE_FIELD: &str = "stack_trace";
+
+pub(crate) fn format_gcp(entry: &LogEntry, options: &FormatOptions) -> String {
+    let (labels, fields) = split_label_fields(&entry.fields, &options.label_fields);
+    let trace_context = entry.trace_context.as_ref();
+    // Once Rocket has updated logging more info can be provided:
+    // https://github.com/SergioBenitez/Rocket/issues/21
+    // https://github.com/SergioBenitez/Rocket/pull/1579
+    let log_entry = GoogleCloudStructLog {
+        severity: Some(match entry.level {
+            _ if entry.critical => GCLogSeverity::Critical,
+            Level::Error => GCLogSeverity::Error,
+            Level::Warn => GCLogSeverity::Warning,
+            Level::Info => GCLogSeverity::Info,
+            Level::Debug => GCLogSeverity::Debug,
+            Level::Trace => GCLogSeverity::Default,
+        }),
+        report_type: match entry.level {
+            // More info see: https://cloud.google.com/error-reporting/docs/formatting-error-messages#@type
+            Level::Error => Some("type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent".to_owned()),
+            _ => None,
+        },
+        message: Some(entry.message.clone()),
+        operation: Some(GCOperation {
+            id: Some(&options.id),
+            producer: Some(&options.producer),
+            ..Default::default()
+        }),
+        source_location: Some(GCSourceLocation {
+            file: entry.file.as_deref(),
+            line: entry.line.map(|lineno| lineno.to_string()),
+            function: entry.module_path.as_deref(),
+        }),
+        time: Some(entry.time),
+        labels,
+        trace: trace_context.map(|trace_context| match &options.trace_project {
+            Some(project_id) => format!(
+                "projects/{}/traces/{}",
+                project_id, trace_context.trace_id
+            ),
+            None => trace_context.trace_id.clone(),
+        }),
+        span_id: trace_context.and_then(|trace_context| trace_context.span_id.clone()),
+        trace_sampled: trace_context.map(|trace_context| trace_context.sampled),
+        ..Default::default()
+    };
+    let mut log_entry = serde_json::to_value(&log_entry).expect("Error during logging");
+    // Extra fields end up in the `jsonPayload` of the log entry.
+    if let Value::Object(log_entry) = &mut log_entry {
+        if let Some(http_request) = &entry.http_request {
+            log_entry.insert("httpRequest".to_owned(), http_request.clone());
+        }
+        if !entry.backtrace.is_empty() {
+            log_entry.insert(
+                STACK_TRACE_FIELD.to_owned(),
+                Value::from(stack_trace(&entry.message, &entry.backtrace)),
+            );
+        }
+        for (key, value) in fields {
+            log_entry.entry(key).or_insert(value);
+        }
+    }
+    log_entry.to_string()
+}
+
+/// The message followed by the frames in the format that Error Reporting parses:
+/// `    at function (file:line:column)`.
+fn stack_trace(message: &str, backtrace: &str) -> String {
+    let mut stack_trace = message.to_owned();
+    for frame in backtrace
+        .lines()
+        .map(str::trim)
+        .filter(|frame| !frame.is_empty())
+    {
+        match frame.rsplit_once(" at ") {
+            Some((function, location)) => {
+                stack_trace.push_str(&format!("\n    at {} ({})", function, location))
+            }
+            None => stack_trace.push_str(&format!("\n    at {}", frame)),
+        }
+    }
+    stack_trace
+}
@@ -0,0 +1,60 @@
+use crate::format_text::format_text_value;
+use crate::{FormatOptions, LogEntry};
+use chrono::SecondsFormat;
+use serde_json::Value;
+
+/// Format as `key=value` pairs: https://brandur.org/logfmt
+pub(crate) fn format_logfmt(entry: &LogEntry, options: &FormatOptions) -> String {
+    let mut pairs: Vec<(String, Value)> = vec![
+        (
+            "time".to_owned(),
+            Value::from(entry.time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+        ),
+        ("level".to_owned(), Value::from(level_name(entry))),
+        ("target".to_owned(), Value::from(entry.target.clone())),
+        ("msg".to_owned(), Value::from(entry.message.clone())),
+        ("service".to_owned(), Value::from(options.producer.clone())),
+    ];
+    if let Some(Value::Object(http_request)) = &entry.http_request {
+        for (key, value) in http_request {
+            pairs.push((format!("http_request.{}", key), value.clone()));
+        }
+    }
+    for (key, value) in &entry.fields {
+        pairs.push((key.clone(), value.clone()));
+    }
+    if let Some(trace_context) = &entry.trace_context {
+        pairs.push((
+            "trace_id".to_owned(),
+            Value::from(trace_context.trace_id.clone()),
+        ));
+        if let Some(span_id) = &trace_context.span_id {
+            pairs.push(("span_id".to_owned(), Value::from(span_id.clone())));
+        }
+    }
+    if let Some(file) = &entry.file {
+        pairs.push(("file".to_owned(), Value::from(file.clone())));
+    }
+    if let Some(line) = entry.line {
+        pairs.push(("line".to_owned(), Value::from(line)));
+    }
+    if !entry.backtrace.is_empty() {
+        pairs.push((
+            "backtrace".to_owned(),
+            Value::from(entry.backtrace.trim_start()),
+        ));
+    }
+    pairs
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, format_text_value(&value)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn level_name(entry: &LogEntry) -> String {
+    if entry.critical {
+        "critical".to_owned()
+    } else {
+        entry.level.as_str().to_lowercase()
+    }
+}
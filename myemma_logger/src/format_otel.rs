@@ -0,0 +1,71 @@
+use crate::format_ecs::latency_nanos;
+use crate::{FormatOptions, LogEntry};
+use log::Level;
+use serde_json::{json, Map, Value};
+
+/// Format as JSON with the fields of the OpenTelemetry log data model:
+/// https://opentelemetry.io/docs/specs/otel/logs/data-model/
+pub(crate) fn format_otel(entry: &LogEntry, options: &FormatOptions) -> String {
+    let mut attributes = Map::new();
+    let mut insert = |key: &str, value: Value| {
+        if !value.is_null() {
+            attributes.insert(key.to_owned(), value);
+        }
+    };
+    insert("code.filepath", json!(entry.file));
+    insert("code.lineno", json!(entry.line));
+    insert("code.namespace", json!(entry.module_path));
+    if !entry.backtrace.is_empty() {
+        insert("exception.stacktrace", json!(entry.backtrace.trim_start()));
+    }
+    if let Some(http_request) = &entry.http_request {
+        insert("http.request.method", http_request["requestMethod"].clone());
+        insert("url.full", http_request["requestUrl"].clone());
+        insert("http.response.status_code", http_request["status"].clone());
+        insert("user_agent.original", http_request["userAgent"].clone());
+        insert("client.address", http_request["remoteIp"].clone());
+        insert(
+            "http.server.request.duration_ns",
+            latency_nanos(&http_request["latency"]),
+        );
+    }
+    for (key, value) in &entry.fields {
+        attributes.entry(key.clone()).or_insert(value.clone());
+    }
+
+    let (severity_text, severity_number) = match entry.level {
+        _ if entry.critical => ("FATAL", 21),
+        Level::Error => ("ERROR", 17),
+        Level::Warn => ("WARN", 13),
+        Level::Info => ("INFO", 9),
+        Level::Debug => ("DEBUG", 5),
+        Level::Trace => ("TRACE", 1),
+    };
+    let timestamp = entry
+        .time
+        .timestamp_nanos_opt()
+        .map(|nanos| nanos.to_string());
+    let mut log_record = json!({
+        "Timestamp": timestamp,
+        "ObservedTimestamp": timestamp,
+        "SeverityText": severity_text,
+        "SeverityNumber": severity_number,
+        "Body": entry.message,
+        "Resource": {
+            "service.name": options.producer,
+            "service.instance.id": options.id,
+        },
+        "InstrumentationScope": {
+            "Name": entry.target,
+        },
+        "Attributes": attributes,
+    });
+    if let Some(trace_context) = &entry.trace_context {
+        log_record["TraceId"] = json!(trace_context.trace_id);
+        if let Some(span_id) = &trace_context.span_id {
+            log_record["SpanId"] = json!(span_id);
+        }
+        log_record["TraceFlags"] = json!(u8::from(trace_context.sampled));
+    }
+    log_record.to_string()
+}
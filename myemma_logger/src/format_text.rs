@@ -0,0 +1,289 @@
+use crate::{LogEntry, LogSink};
+use log::Level;
+use serde_json::{Map, Value};
+use yansi::{Condition, Paint, Painted};
+
+/// Template of a Text line: `WARN :services::billing - Invoice failed user_id=5`
+pub const DEFAULT_TEXT_TEMPLATE: &str = "{level}:{target} - {message}{fields}";
+
+/// Format of `{time}` in the template, for example `2022-03-01T14:05:09.120Z`.
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+/// Indentation of the continuation lines of multi-line messages and backtraces.
+const INDENT: &str = "    ";
+
+/// When the Text format uses ANSI colors.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Only when the sink writes to a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Whether lines written to `sink` should be colored.
+    pub fn enabled_for(&self, sink: &LogSink) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            // More info see: https://no-color.org/
+            ColorMode::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && sink.is_terminal()
+            }
+        }
+    }
+}
+
+/// Layout of the Text format.
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+    /// Line template with the placeholders `{time}`, `{level}`, `{target}`, `{message}`,
+    /// `{fields}`, `{file}`, `{line}` and `{module}`.
+    /// The backtrace is always added at the end.
+    pub template: String,
+    /// `chrono` format of `{time}`, the time is in UTC.
+    pub timestamp_format: String,
+    pub color: ColorMode,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_TEXT_TEMPLATE.to_owned(),
+            timestamp_format: DEFAULT_TIMESTAMP_FORMAT.to_owned(),
+            color: ColorMode::default(),
+        }
+    }
+}
+
+impl TextOptions {
+    pub fn with_template(mut self, template: &str) -> Self {
+        self.template = template.to_owned();
+        self
+    }
+
+    /// Start every line with a timestamp in `timestamp_format`,
+    /// unless the template already contains `{time}`.
+    pub fn with_timestamps(mut self, timestamp_format: &str) -> Self {
+        self.timestamp_format = timestamp_format.to_owned();
+        if !self.template.contains("{time}") {
+            self.template = format!("{{time}} {}", self.template);
+        }
+        self
+    }
+
+    pub fn with_color(mut self, color: ColorMode) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+pub(crate) fn format_text(entry: &LogEntry, options: &TextOptions, colored: bool) -> String {
+    let backtrace = indent_backtrace(&entry.backtrace);
+    if entry.critical {
+        return format!(
+            "PANIC:{} - {}:{}{}",
+            indent_lines(&entry.message),
+            entry.file.as_deref().unwrap_or("unknown file"),
+            entry.line.unwrap_or_default(),
+            backtrace,
+        );
+    }
+    let condition = if colored {
+        Condition::ALWAYS
+    } else {
+        Condition::NEVER
+    };
+    let mut line = String::new();
+    let mut rest = options.template.as_str();
+    while let Some(start) = rest.find('{') {
+        line.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        match &rest[1..end] {
+            "time" => line.push_str(&entry.time.format(&options.timestamp_format).to_string()),
+            "level" => line.push_str(&format_level(entry.level, condition)),
+            "target" => line.push_str(
+                &Paint::new(&entry.target)
+                    .dim()
+                    .whenever(condition)
+                    .to_string(),
+            ),
+            "message" => line.push_str(&match &entry.http_request {
+                Some(http_request) => format_text_http_request(http_request, condition),
+                None => indent_lines(&entry.message),
+            }),
+            "fields" => line.push_str(&format_text_fields(&entry.fields)),
+            "file" => line.push_str(entry.file.as_deref().unwrap_or("-")),
+            "line" => line.push_str(
+                &entry
+                    .line
+                    .map(|line| line.to_string())
+                    .unwrap_or_else(|| "-".to_owned()),
+            ),
+            "module" => line.push_str(entry.module_path.as_deref().unwrap_or("-")),
+            // Unknown placeholders are kept as they are.
+            _ => line.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    line.push_str(rest);
+    line.push_str(&backtrace);
+    line
+}
+
+/// The level is padded to 5 characters, so the targets are aligned.
+fn format_level(level: Level, condition: Condition) -> String {
+    let name = format!("{:<5}", level.as_str());
+    let painted = match level {
+        Level::Error => Paint::red(&name),
+        Level::Warn => Paint::yellow(&name),
+        Level::Info => Paint::blue(&name),
+        Level::Debug => Paint::green(&name),
+        Level::Trace => Paint::magenta(&name),
+    };
+    painted.whenever(condition).to_string()
+}
+
+/// Indent the continuation lines, so they are not confused with new log lines.
+fn indent_lines(text: &str) -> String {
+    text.trim_end().replace('\n', &format!("\n{}", INDENT))
+}
+
+/// Put every frame of the backtrace on its own indented line.
+fn indent_backtrace(backtrace: &str) -> String {
+    backtrace
+        .lines()
+        .map(str::trim)
+        .filter(|frame| !frame.is_empty())
+        .map(|frame| format!("\n{}{}", INDENT, frame))
+        .collect()
+}
+
+/// Format the fields as ` key=value` pairs, nested values are printed as JSON.
+pub(crate) fn format_text_fields(fields: &Map<String, Value>) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| format!(" {}={}", key, format_text_value(value)))
+        .collect()
+}
+
+/// Format the `http_request` field as a compact line: `GET /users 200 0.001200s 532B`.
+fn format_text_http_request(http_request: &Value, condition: Condition) -> String {
+    let text = |key: &str| match &http_request[key] {
+        Value::String(text) => text.clone(),
+        Value::Null => "-".to_owned(),
+        other => other.to_string(),
+    };
+    let status = http_request["status"].as_u64().unwrap_or_default();
+    let painted_status: Painted<&u64> = match status {
+        500.. => Paint::red(&status),
+        400.. => Paint::yellow(&status),
+        300.. => Paint::cyan(&status),
+        _ => Paint::green(&status),
+    };
+    // The size is unknown for streamed bodies.
+    let size = match &http_request["responseSize"] {
+        Value::Null => "-".to_owned(),
+        _ => format!("{}B", text("responseSize")),
+    };
+    format!(
+        "{} {} {} {} {}",
+        Paint::new(text("requestMethod")).bold().whenever(condition),
+        text("requestUrl"),
+        painted_status.whenever(condition),
+        Paint::new(text("latency")).dim().whenever(condition),
+        size,
+    )
+}
+
+/// Strings are quoted when needed, other values are printed as JSON.
+pub(crate) fn format_text_value(value: &Value) -> String {
+    match value {
+        Value::String(text)
+            if text.is_empty()
+                || text.contains(|c: char| {
+                    c.is_whitespace() || c.is_control() || c == '=' || c == '"'
+                }) =>
+        {
+            format!("{:?}", text)
+        }
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry() -> LogEntry {
+        let mut entry = LogEntry::new(
+            Level::Warn,
+            "services::billing",
+            "Invoice failed\nretrying".to_owned(),
+        );
+        entry.fields.insert("user_id".to_owned(), Value::from(5));
+        entry.backtrace =
+            "\n   billing::invoice at src/billing.rs:12:5\n   main at src/main.rs:3:1".to_owned();
+        entry.time = chrono::Utc.with_ymd_and_hms(2022, 3, 1, 14, 5, 9).unwrap();
+        entry
+    }
+
+    #[test]
+    fn test_default_template_without_colors() {
+        assert_eq!(
+            "WARN :services::billing - Invoice failed\n    retrying user_id=5\
+             \n    billing::invoice at src/billing.rs:12:5\n    main at src/main.rs:3:1",
+            format_text(&entry(), &TextOptions::default(), false)
+        );
+    }
+
+    #[test]
+    fn test_template_with_timestamps() {
+        let options = TextOptions::default()
+            .with_template("[{level}] {message} ({unknown})")
+            .with_timestamps("%H:%M:%S");
+        let mut entry = entry();
+        entry.backtrace = String::new();
+        assert_eq!(
+            "14:05:09 [WARN ] Invoice failed\n    retrying ({unknown})",
+            format_text(&entry, &options, false)
+        );
+    }
+
+    #[test]
+    fn test_http_request() {
+        let mut http_request = serde_json::json!({
+            "requestMethod": "GET",
+            "requestUrl": "/users",
+            "status": 200,
+            "latency": "0.001200s",
+            "responseSize": "532",
+        });
+        assert_eq!(
+            "GET /users 200 0.001200s 532B",
+            format_text_http_request(&http_request, Condition::NEVER)
+        );
+        http_request.as_object_mut().unwrap().remove("responseSize");
+        assert_eq!(
+            "GET /users 200 0.001200s -",
+            format_text_http_request(&http_request, Condition::NEVER)
+        );
+    }
+
+    #[test]
+    fn test_colors() {
+        let options = TextOptions::default();
+        assert!(format_text(&entry(), &options, true).contains('\u{1b}'));
+        assert!(!format_text(&entry(), &options, false).contains('\u{1b}'));
+        assert!(!ColorMode::Auto.enabled_for(&LogSink::Memory(Default::default())));
+        assert!(ColorMode::Always.enabled_for(&LogSink::Memory(Default::default())));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{LogEntry, LogFilter, Logger};
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use myemma_backtrace::BacktraceFilter;
+use regex::Regex;
+use std::sync::{Arc, OnceLock};
+
+/// The installed `Logger` or `FanOutLogger`, so panics can be logged with it.
+static INSTALLED: OnceLock<Arc<dyn EntryLog>> = OnceLock::new();
+
+/// A logger that can also log a complete entry, used for panics.
+pub(crate) trait EntryLog: Log {
+    /// Log the entry without checking the `LogFilter`.
+    fn log_complete_entry(&self, entry: &LogEntry);
+
+    /// The most verbose level that is currently enabled, it changes when a filter is reloaded.
+    fn max_level(&self) -> LevelFilter;
+}
+
+/// The global logger, shares the logger with `INSTALLED`.
+struct InstalledLogger(Arc<dyn EntryLog>);
+
+impl Log for InstalledLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.0.log(record)
+    }
+
+    fn flush(&self) {
+        self.0.flush()
+    }
+}
+
+pub(crate) fn install_logger(logger: Arc<dyn EntryLog>) -> Result<LoggerGuard, SetLoggerError> {
+    log::set_boxed_logger(Box::new(InstalledLogger(logger.clone())))?;
+    log::set_max_level(logger.max_level());
+    // Only one logger can be installed, so this is always the first.
+    let _ = INSTALLED.set(logger);
+    crate::directive_filter::log_default_error();
+    crate::log_format::log_unknown_format();
+    Ok(LoggerGuard::new())
+}
+
+/// The max level of the installed logger, `None` when no logger of this crate was installed.
+pub(crate) fn installed_max_level() -> Option<LevelFilter> {
+    INSTALLED.get().map(|logger| logger.max_level())
+}
+
+/// Log a panic with the installed `Logger` or `FanOutLogger`, so it gets the same
+/// redaction, format and sink as all other entries. The logger is flushed afterwards.
+///
+/// Returns `false` when no logger of this crate was installed.
+pub fn log_panic(entry: &LogEntry) -> bool {
+    match INSTALLED.get() {
+        Some(logger) => {
+            logger.log_complete_entry(entry);
+            logger.flush();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Flushes the installed logger when dropped, keep it alive until the end of `main`
+/// so no log lines (or summaries of suppressed messages) are lost at shutdown.
+#[derive(Debug)]
+#[must_use = "The logger is flushed when the guard is dropped"]
+pub struct LoggerGuard {
+    _private: (),
+}
+
+impl LoggerGuard {
+    pub(crate) fn new() -> Self {
+        Self { _private: () }
+    }
+}
+
+impl Drop for LoggerGuard {
+    fn drop(&mut self) {
+        log::logger().flush();
+    }
+}
+
+impl<B, L> Logger<B, L>
+where
+    B: BacktraceFilter + Sized + Send + Sync + 'static,
+    L: LogFilter + Sized + Send + Sync + Default + 'static,
+{
+    /// Register this logger as the global logger and set the max level of the `log` crate
+    /// to the max level of the `LogFilter`.
+    pub fn try_install(self) -> Result<LoggerGuard, SetLoggerError> {
+        install_logger(Arc::new(self))
+    }
+
+    /// Same as `try_install`, but panics when a global logger was already registered.
+    pub fn install(self) -> LoggerGuard {
+        self.try_install()
+            .expect("Logger should be installed before any other logger")
+    }
+}
+
+impl<B, L> EntryLog for Logger<B, L>
+where
+    B: BacktraceFilter + Sized + Send + Sync,
+    L: LogFilter + Sized + Send + Sync + Default,
+{
+    fn log_complete_entry(&self, entry: &LogEntry) {
+        self.log_entry(entry.clone());
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.log_filter.max_level()
+    }
+}
+
+/// Rocket uses its own log conventions, this turns them into normal entries:
+/// targets ending with `::_` (indented lines) are shortened, launch messages are
+/// logged as `Info` instead of `Warn` and the ANSI colors in the messages are removed.
+pub(crate) fn bridge_rocket_entry(entry: &mut LogEntry) {
+    if entry.target != "rocket" && !entry.target.starts_with("rocket::") {
+        return;
+    }
+    if let Some(target) = entry.target.strip_suffix("::_") {
+        entry.target = target.to_owned();
+    }
+    if entry.target == "rocket::launch" && entry.level == Level::Warn {
+        entry.level = Level::Info;
+    }
+    static ANSI_ESCAPE: OnceLock<Regex> = OnceLock::new();
+    let ansi_escape = ANSI_ESCAPE
+        .get_or_init(|| Regex::new(r"\x1b\[[0-9;]*m").expect("ANSI escape pattern is valid"));
+    entry.message = ansi_escape.replace_all(&entry.message, "").into_owned();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bridge_rocket_entry() {
+        let mut entry = LogEntry::new(
+            Level::Warn,
+            "rocket::launch::_",
+            "\u{1b}[1maddress\u{1b}[0m: 127.0.0.1".to_owned(),
+        );
+        bridge_rocket_entry(&mut entry);
+        assert_eq!("rocket::launch", entry.target);
+        assert_eq!(Level::Info, entry.level);
+        assert_eq!("address: 127.0.0.1", entry.message);
+
+        let mut entry = LogEntry::new(Level::Warn, "rocket_cors", "Warning".to_owned());
+        bridge_rocket_entry(&mut entry);
+        assert_eq!(Level::Warn, entry.level);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::syslog::severity;
+use crate::{FormatOptions, LogEntry};
+use log::Level;
+use serde_json::Value;
+use std::io;
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+#[cfg(unix)]
+use std::sync::Arc;
+
+/// Socket of the native protocol of journald.
+pub const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Fields that are set by the sink or by journald itself,
+/// structured fields with these names get a `FIELD_` prefix.
+const RESERVED_FIELDS: &[&str] = &[
+    "MESSAGE",
+    "MESSAGE_ID",
+    "PRIORITY",
+    "SYSLOG_IDENTIFIER",
+    "SYSLOG_FACILITY",
+    "SYSLOG_PID",
+    "OPERATION_ID",
+    "PRODUCER",
+    "TARGET",
+    "CODE_FILE",
+    "CODE_LINE",
+    "CODE_FUNC",
+    "CODE_MODULE",
+    "ERRNO",
+];
+
+/// Sends every line to journald with its native protocol.
+///
+/// The formatted line is the `MESSAGE`, the source location (`CODE_FILE`, `CODE_LINE`,
+/// `CODE_MODULE`), `TARGET`, `OPERATION_ID`, `PRODUCER` and the structured fields
+/// (uppercase, `FIELD_` prefix for the `RESERVED_FIELDS`) are separate journal fields.
+/// The producer is the `SYSLOG_IDENTIFIER`.
+/// Entries larger than a datagram are dropped.
+/// Clones send over the same socket.
+#[derive(Debug, Clone)]
+pub struct JournaldSink {
+    #[cfg(unix)]
+    socket: Arc<UnixDatagram>,
+}
+
+impl JournaldSink {
+    /// Connect to journald at `JOURNALD_SOCKET`.
+    pub fn new() -> io::Result<Self> {
+        Self::connect(JOURNALD_SOCKET)
+    }
+
+    /// Connect to a journald socket at another path.
+    #[cfg(unix)]
+    pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self {
+            socket: Arc::new(socket),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn connect<P: AsRef<Path>>(_path: P) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "journald is only available on Unix",
+        ))
+    }
+
+    pub(crate) fn send_entry(&self, entry: &LogEntry, line: &str, options: &FormatOptions) {
+        let mut message = Vec::new();
+        add_field(&mut message, "MESSAGE", line);
+        add_field(
+            &mut message,
+            "PRIORITY",
+            &severity(entry.level, entry.critical).to_string(),
+        );
+        add_field(&mut message, "SYSLOG_IDENTIFIER", &options.producer);
+        add_field(&mut message, "OPERATION_ID", &options.id);
+        add_field(&mut message, "PRODUCER", &options.producer);
+        add_field(&mut message, "TARGET", &entry.target);
+        if let Some(file) = &entry.file {
+            add_field(&mut message, "CODE_FILE", file);
+        }
+        if let Some(line) = entry.line {
+            add_field(&mut message, "CODE_LINE", &line.to_string());
+        }
+        if let Some(module_path) = &entry.module_path {
+            add_field(&mut message, "CODE_MODULE", module_path);
+        }
+        for (key, value) in &entry.fields {
+            let Some(name) = field_name(key) else {
+                continue;
+            };
+            match value {
+                Value::String(text) => add_field(&mut message, &name, text),
+                other => add_field(&mut message, &name, &other.to_string()),
+            }
+        }
+        self.send(&message);
+    }
+
+    /// Send a line without the other fields, for example from an `AsyncWriter`.
+    pub(crate) fn send_line(&self, level: Level, line: &str) {
+        let mut message = Vec::new();
+        add_field(&mut message, "MESSAGE", line);
+        add_field(
+            &mut message,
+            "PRIORITY",
+            &severity(level, false).to_string(),
+        );
+        self.send(&message);
+    }
+
+    fn send(&self, message: &[u8]) {
+        // Errors are ignored, just like the other sinks.
+        #[cfg(unix)]
+        let _ = self.socket.send(message);
+        #[cfg(not(unix))]
+        let _ = message;
+    }
+}
+
+/// Values with a newline are prefixed with their length, all others are `NAME=value`.
+fn add_field(message: &mut Vec<u8>, name: &str, value: &str) {
+    message.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        message.push(b'\n');
+        message.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        message.push(b'=');
+    }
+    message.extend_from_slice(value.as_bytes());
+    message.push(b'\n');
+}
+
+/// Journal field names are uppercase letters, digits and `_`, and do not start with `_`
+/// or a digit. Returns `None` when nothing is left of the key.
+fn field_name(key: &str) -> Option<String> {
+    let name: String = key
+        .chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            c @ ('A'..='Z' | '0'..='9') => c,
+            _ => '_',
+        })
+        .collect();
+    let name = name.trim_start_matches(|c: char| c == '_' || c.is_ascii_digit());
+    if name.is_empty() {
+        None
+    } else if RESERVED_FIELDS.contains(&name) {
+        Some(format!("FIELD_{}", name))
+    } else {
+        Some(name.chars().take(64).collect())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_entry() {
+        let path = std::env::temp_dir().join(format!("myemma_journald_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+        let sink = JournaldSink::connect(&path).unwrap();
+        let mut entry = LogEntry::new(Level::Error, "services::billing", "Failed".to_owned());
+        entry.line = Some(12);
+        entry.fields.insert("invoice-id".to_owned(), Value::from(5));
+        sink.send_entry(
+            &entry,
+            "Failed\n    at main",
+            &FormatOptions::new("op1", "billing"),
+        );
+
+        let mut buffer = [0; 1024];
+        let size = server.recv(&mut buffer).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&18u64.to_le_bytes());
+        expected.extend_from_slice(
+            b"Failed\n    at main\nPRIORITY=3\nSYSLOG_IDENTIFIER=billing\nOPERATION_ID=op1\n\
+              PRODUCER=billing\nTARGET=services::billing\nCODE_LINE=12\nINVOICE_ID=5\n",
+        );
+        assert_eq!(expected, buffer[..size]);
+    }
+
+    #[test]
+    fn test_field_name() {
+        assert_eq!(Some("USER_ID".to_owned()), field_name("user.id"));
+        assert_eq!(Some("ID".to_owned()), field_name("_1id"));
+        assert_eq!(None, field_name("__"));
+        assert_eq!(Some("FIELD_MESSAGE".to_owned()), field_name("message"));
+    }
+}
@@ -1,32 +1,119 @@
 #![forbid(unsafe_code)]
 #![deny(clippy::all)]
 
-use chrono::Utc;
-use google_cloud_logging::{GCLogSeverity, GCOperation, GCSourceLocation, GoogleCloudStructLog};
-use log::{Level, Metadata, Record};
+use log::{Metadata, Record};
 use myemma_backtrace::BacktraceFilter;
 use std::marker::PhantomData;
+use std::sync::Arc;
 /// Re-export Yansi Paint so painter can be disabled: `Paint::disable();`
 pub use yansi::Paint;
 
+// Reexport crate
+pub use log;
+
 mod log_format;
-pub use log_format::LogFormat;
+pub use log_format::{FormatOptions, LogFormat, UnknownLogFormat};
+
+mod log_entry;
+pub use log_entry::LogEntry;
+
+mod format_ecs;
+mod format_gcp;
+mod format_logfmt;
+mod format_otel;
+mod format_text;
+pub use format_text::{ColorMode, TextOptions, DEFAULT_TEXT_TEMPLATE, DEFAULT_TIMESTAMP_FORMAT};
 
 mod log_filter;
 pub use log_filter::LogFilter;
 
+mod backtrace_policy;
+pub use backtrace_policy::{BacktracePolicy, DEFAULT_BACKTRACE_DIRECTIVES};
+
+mod filter_combinators;
+pub use filter_combinators::{
+    AndFilter, BoxedFilter, FnFilter, LevelLogFilter, LogFilterExt, ModuleFilter, NotFilter,
+    OrFilter, TargetPrefixFilter,
+};
+
+mod directive_filter;
+pub use directive_filter::{DirectiveError, DirectiveFilter, DEFAULT_DIRECTIVES};
+
+mod reload;
+pub use reload::{ReloadHandle, ReloadSource, ReloadableFilter};
+
+mod log_fields;
+pub use log_fields::HTTP_REQUEST_FIELD;
+
+mod log_sink;
+pub use log_sink::{FlushGuard, LogSink, MemorySink};
+
+mod rotating_file;
+pub use rotating_file::{RotatingFile, RotatingFileConfig, Rotation};
+
+mod syslog;
+pub use syslog::{
+    SyslogFacility, SyslogSink, DEFAULT_SYSLOG_MESSAGE_SIZE, SYSLOG_SD_ID, SYSLOG_SOCKET,
+};
+
+mod journald;
+pub use journald::{JournaldSink, JOURNALD_SOCKET};
+
+mod trace_context;
+pub use trace_context::{TraceContext, TraceContextGuard};
+
+mod async_writer;
+pub use async_writer::{AsyncWriter, AsyncWriterConfig, OverflowPolicy};
+
+mod install;
+use install::bridge_rocket_entry;
+pub use install::{log_panic, LoggerGuard};
+
+#[cfg(feature = "test-util")]
+mod test_logger;
+#[cfg(feature = "test-util")]
+pub use test_logger::{
+    captured_records, clear_captured_records, init_test_logger, is_logged, CapturedRecord,
+    TestLogger,
+};
+
+mod fan_out;
+pub use fan_out::FanOutLogger;
+
+mod redactor;
+pub use redactor::{Redactor, DEFAULT_REDACTED_KEYS, REDACTED};
+
+mod log_sampler;
+pub use log_sampler::LogSampler;
+
+mod duplicate_filter;
+pub use duplicate_filter::DuplicateConfig;
+use duplicate_filter::DuplicateFilter;
+
+#[cfg(feature = "tracing")]
+mod tracing_layer;
+
 /// The log collector and handler for most printed messages in terminal.
+///
+/// The `LogFilter` defaults to a `BoxedFilter`, so it can be assembled at runtime.
 #[derive(Debug)]
-pub struct Logger<B, L>
+pub struct Logger<B, L = BoxedFilter>
 where
     B: BacktraceFilter + Sized + Send + Sync,
     L: LogFilter + Sized + Send + Sync + Default,
 {
-    id: String,
-    producer: String,
+    options: FormatOptions,
     format: LogFormat,
     backtrace_count: u16,
+    backtrace_policy: BacktracePolicy,
     log_filter: L,
+    sink: LogSink,
+    /// Every sink is wrapped in an `AsyncWriter` with this config.
+    async_writer: Option<AsyncWriterConfig>,
+    rocket_bridge: bool,
+    sampler: Option<LogSampler>,
+    redactor: Option<Redactor>,
+    duplicate_filter: Option<Arc<DuplicateFilter>>,
     _backtrace_filter: PhantomData<B>,
 }
 
@@ -47,11 +134,17 @@ where
 {
     pub fn new(id: &str, producer: &str) -> Self {
         Self {
-            id: id.to_string(),
-            producer: producer.to_string(),
+            options: FormatOptions::new(id, producer),
             format: LogFormat::get_format(),
             backtrace_count: 4,
+            backtrace_policy: BacktracePolicy::default(),
             log_filter: L::default(),
+            sink: LogSink::default(),
+            async_writer: None,
+            rocket_bridge: false,
+            sampler: None,
+            redactor: None,
+            duplicate_filter: None,
             _backtrace_filter: PhantomData,
         }
     }
@@ -61,15 +154,118 @@ where
         self
     }
 
+    /// Set the sink, it is written to on a background thread after `with_async_writer`.
+    pub fn with_sink(mut self, sink: LogSink) -> Self {
+        self.options = self.options.with_colors_for(&sink);
+        self.sink = match &self.async_writer {
+            Some(config) => self.start_async_writer(sink, config),
+            None => sink,
+        };
+        self
+    }
+
+    /// Change the timestamps, template and colors of the Text format.
+    pub fn with_text_options(mut self, text_options: TextOptions) -> Self {
+        self.options.text = text_options;
+        self.options = self.options.with_colors_for(&self.sink);
+        self
+    }
+
+    /// Write the lines on a background thread to the sink,
+    /// also to sinks that are set later with `with_sink`.
+    pub fn with_async_writer(mut self, config: AsyncWriterConfig) -> Self {
+        let sink = match std::mem::take(&mut self.sink) {
+            LogSink::Async(writer) => writer.sink().clone(),
+            sink => sink,
+        };
+        self.sink = self.start_async_writer(sink, &config);
+        self.async_writer = Some(config);
+        self
+    }
+
+    /// Wrap the sink in an `AsyncWriter`. When its thread can not be started the sink is
+    /// written to directly, with a warning in the log.
+    fn start_async_writer(&self, sink: LogSink, config: &AsyncWriterConfig) -> LogSink {
+        match AsyncWriter::new(sink.clone(), config.clone()) {
+            Ok(writer) => LogSink::Async(writer),
+            Err(err) => {
+                let entry = LogEntry::new(
+                    log::Level::Warn,
+                    module_path!(),
+                    format!(
+                        "Could not start the log writer thread, writing synchronously: {}",
+                        err
+                    ),
+                );
+                let line = self.format.format_entry(&entry, &self.options);
+                sink.write_entry(&entry, &line, &self.options);
+                sink
+            }
+        }
+    }
+
+    /// Get a guard that flushes the sink of this logger when dropped.
+    /// Create it before the logger is registered with `log::set_boxed_logger`.
+    pub fn flush_guard(&self) -> FlushGuard {
+        FlushGuard::new(self.sink.clone())
+    }
+
+    /// Structured fields with these keys are added as labels in the Json format,
+    /// all other fields are added to the `jsonPayload`.
+    pub fn with_label_fields(mut self, label_fields: &[&str]) -> Self {
+        self.options.label_fields = label_fields.iter().map(|key| key.to_string()).collect();
+        self
+    }
+
+    /// The Google Cloud project of the traces, used to link log entries to a trace.
+    /// Defaults to the `GOOGLE_CLOUD_PROJECT` environment variable.
+    pub fn with_trace_project(mut self, project_id: &str) -> Self {
+        self.options.trace_project = Some(project_id.to_owned());
+        self
+    }
+
     pub fn with_backtrace_count(mut self, backtrace_count: u16) -> Self {
         self.backtrace_count = backtrace_count;
         self
     }
 
+    /// For which levels and targets a backtrace is logged, defaults to errors and warnings.
+    pub fn with_backtrace_policy(mut self, backtrace_policy: BacktracePolicy) -> Self {
+        self.backtrace_policy = backtrace_policy;
+        self
+    }
+
     pub fn with_log_filter(mut self, log_filter: L) -> Self {
         self.log_filter = log_filter;
         self
     }
+
+    /// Clean up the log messages of Rocket, see `Logger::install`.
+    /// Rocket only uses its own logger when no other logger is installed.
+    pub fn with_rocket_bridge(mut self) -> Self {
+        self.rocket_bridge = true;
+        self
+    }
+
+    /// Only log a fraction of the entries per level and target, see `LogSampler`.
+    pub fn with_sampler(mut self, sampler: LogSampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// Remove secrets from messages and fields before anything is written.
+    /// Keep a clone of the redactor to read the `redaction_count`.
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Collapse identical consecutive messages of the same target and level
+    /// into a single "previous message repeated N times" entry.
+    pub fn with_duplicate_suppression(mut self, config: DuplicateConfig) -> Self {
+        self.duplicate_filter = Some(Arc::new(DuplicateFilter::new(config)));
+        self
+    }
 }
 
 impl<B, L> log::Log for Logger<B, L>
@@ -83,72 +279,265 @@ where
 
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
-            let level = record.level();
-            match self.format {
-                LogFormat::Text => {
-                    println!(
-                        "{:<5}:{} - {}{}",
-                        match level {
-                            Level::Error => Paint::red("ERROR"),
-                            Level::Warn => Paint::yellow("WARN"),
-                            Level::Info => Paint::blue("INFO"),
-                            Level::Debug => Paint::green("DEBUG"),
-                            Level::Trace => Paint::magenta("TRACE"),
-                        },
-                        Paint::new(record.target()).dim(),
-                        record.args(),
-                        match level {
-                            Level::Error | Level::Warn =>
-                                B::get_backtrace_info(self.backtrace_count),
-                            _ => "".to_owned(),
-                        }
-                    );
-                }
-                LogFormat::Json => {
-                    // Once Rocket has updated logging more info can be provided:
-                    // https://github.com/SergioBenitez/Rocket/issues/21
-                    // https://github.com/SergioBenitez/Rocket/pull/1579
-                    let log_entry = GoogleCloudStructLog {
-                        severity: Some(match level {
-                            Level::Error => GCLogSeverity::Error,
-                            Level::Warn => GCLogSeverity::Warning,
-                            Level::Info => GCLogSeverity::Info,
-                            Level::Debug => GCLogSeverity::Debug,
-                            Level::Trace => GCLogSeverity::Default,
-                        }),
-                        report_type: match level {
-                            // More info see: https://cloud.google.com/error-reporting/docs/formatting-error-messages#@type
-                            Level::Error => Some("type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent".to_owned()),
-                            _ => None,
-                        },
-                        message: Some(
-                            format!(
-                                "{}{}", 
-                                record.args(),
-                                B::get_backtrace_info(self.backtrace_count)
-                            )
-                        ),
-                        operation: Some(GCOperation {
-                            id: Some(&self.id),
-                            producer: Some(&self.producer),
-                            ..Default::default()
-                        }),
-                        source_location: Some(GCSourceLocation {
-                            file: record.file(),
-                            line: record.line().map(|lineno| lineno.to_string()),
-                            function: record.module_path(),
-                        }),
-                        time: Some(Utc::now()),
-                        ..Default::default()
-                    };
-                    println!(
-                        "{}",
-                        serde_json::to_string(&log_entry).expect("Error during logging")
-                    );
-                }
+            let mut entry = LogEntry::from_record(record);
+            if self.rocket_bridge {
+                bridge_rocket_entry(&mut entry);
             }
+            self.log_entry(entry);
         }
     }
 
-    fn flush(&self) {}
+    /// Also logs the summaries of all suppressed duplicate messages.
+    fn flush(&self) {
+        if let Some(duplicate_filter) = &self.duplicate_filter {
+            for summary in duplicate_filter.drain() {
+                self.write_entry(&summary);
+            }
+        }
+        self.sink.flush();
+    }
+}
+
+impl<B, L> Logger<B, L>
+where
+    B: BacktraceFilter + Sized + Send + Sync,
+    L: LogFilter + Sized + Send + Sync + Default,
+{
+    /// Log an entry that passed the `LogFilter`.
+    pub(crate) fn log_entry(&self, mut entry: LogEntry) {
+        if let Some(sampler) = &self.sampler {
+            if !sampler.keep(&entry) {
+                return;
+            }
+        }
+        if let Some(redactor) = &self.redactor {
+            redactor.redact_entry(&mut entry);
+        }
+        // Repeated panics are all logged, each with its own backtrace.
+        if let Some(duplicate_filter) = self.duplicate_filter.as_ref().filter(|_| !entry.critical) {
+            let checked = duplicate_filter.check(&entry);
+            for summary in &checked.summaries {
+                self.write_entry(summary);
+            }
+            if checked.suppress {
+                // Log the summary when the window expires, also when nothing is logged anymore.
+                let (format, options, sink) =
+                    (self.format, self.options.clone(), self.sink.clone());
+                duplicate_filter.start_timer(move |summary| {
+                    let line = format.format_entry(summary, &options);
+                    sink.write_entry(summary, &line, &options);
+                });
+                return;
+            }
+        }
+        // Panics come with the backtrace of the panic.
+        if entry.backtrace.is_empty() && self.backtrace_policy.captures(entry.level, &entry.target)
+        {
+            entry.backtrace = B::get_backtrace_info(self.backtrace_count);
+        }
+        self.write_entry(&entry);
+    }
+
+    /// Format the entry to a single line, or multiple lines for Text with a backtrace.
+    fn write_entry(&self, entry: &LogEntry) {
+        let line = self.format.format_entry(entry, &self.options);
+        self.sink.write_entry(entry, &line, &self.options);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use myemma_backtrace::BacktraceFilter;
+
+    /// Keeps no frames, so tests do not depend on the backtrace.
+    pub(crate) struct NoBacktraceFilter;
+
+    impl BacktraceFilter for NoBacktraceFilter {
+        fn filter(_symbol: &myemma_backtrace::backtrace::Symbol) -> bool {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install::EntryLog;
+    use crate::test_util::NoBacktraceFilter;
+    use log::{Level, Log};
+
+    #[derive(Default)]
+    struct AllLogFilter;
+
+    impl LogFilter for AllLogFilter {
+        fn filter(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_log_to_memory_sink() {
+        let memory = MemorySink::default();
+        let logger = Logger::<NoBacktraceFilter, AllLogFilter>::default()
+            .with_format(LogFormat::Json)
+            .with_sink(LogSink::Memory(memory.clone()));
+
+        logger.log(
+            &Record::builder()
+                .args(format_args!("Something happened"))
+                .level(Level::Warn)
+                .target("services::billing")
+                .build(),
+        );
+
+        let lines = memory.lines();
+        assert_eq!(1, lines.len());
+        let entry: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!("warning", entry["severity"]);
+        assert_eq!("Something happened", entry["message"]);
+    }
+
+    #[test]
+    fn test_log_fields_as_json_payload_and_labels() {
+        let memory = MemorySink::default();
+        let logger = Logger::<NoBacktraceFilter, AllLogFilter>::default()
+            .with_format(LogFormat::Json)
+            .with_label_fields(&["user_id"])
+            .with_sink(LogSink::Memory(memory.clone()));
+
+        let key_values = [
+            ("user_id", log::kv::Value::from(5)),
+            ("invoice", log::kv::Value::from("2023-001")),
+        ];
+        logger.log(
+            &Record::builder()
+                .args(format_args!("Invoice sent"))
+                .level(Level::Info)
+                .key_values(&key_values)
+                .build(),
+        );
+
+        let entry: serde_json::Value = serde_json::from_str(&memory.lines()[0]).unwrap();
+        assert_eq!("5", entry["logging.googleapis.com/labels"]["user_id"]);
+        assert_eq!("2023-001", entry["invoice"]);
+        assert!(entry.get("user_id").is_none());
+    }
+
+    #[test]
+    fn test_log_http_request() {
+        let memory = MemorySink::default();
+        let logger = Logger::<NoBacktraceFilter, AllLogFilter>::default()
+            .with_format(LogFormat::Json)
+            .with_sink(LogSink::Memory(memory.clone()));
+
+        let http_request = serde_json::json!({"requestMethod": "GET", "status": 200});
+        let key_values = [(
+            HTTP_REQUEST_FIELD,
+            log::kv::Value::from_serde(&http_request),
+        )];
+        logger.log(
+            &Record::builder()
+                .args(format_args!("GET / 200"))
+                .level(Level::Info)
+                .key_values(&key_values)
+                .build(),
+        );
+
+        let entry: serde_json::Value = serde_json::from_str(&memory.lines()[0]).unwrap();
+        assert_eq!(http_request, entry["httpRequest"]);
+        assert!(entry.get(HTTP_REQUEST_FIELD).is_none());
+    }
+
+    #[test]
+    fn test_log_duplicate_messages() {
+        let memory = MemorySink::default();
+        let logger = Logger::<NoBacktraceFilter, AllLogFilter>::default()
+            .with_format(LogFormat::Logfmt)
+            .with_duplicate_suppression(DuplicateConfig::new(std::time::Duration::from_secs(60)))
+            .with_sink(LogSink::Memory(memory.clone()));
+
+        for _ in 0..3 {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("Connection lost"))
+                    .level(Level::Error)
+                    .target("services::database")
+                    .build(),
+            );
+        }
+        assert_eq!(1, memory.lines().len());
+        logger.flush();
+
+        let lines = memory.lines();
+        assert_eq!(2, lines.len());
+        assert!(lines[1].contains(" msg=\"previous message repeated 2 times\""));
+    }
+
+    #[test]
+    fn test_log_duplicate_panics() {
+        let memory = MemorySink::default();
+        let logger = Logger::<NoBacktraceFilter, AllLogFilter>::default()
+            .with_format(LogFormat::Logfmt)
+            .with_duplicate_suppression(DuplicateConfig::new(std::time::Duration::from_secs(60)))
+            .with_sink(LogSink::Memory(memory.clone()));
+
+        let panic = LogEntry {
+            critical: true,
+            backtrace: "\n   billing::invoice at src/billing.rs:12:5".to_owned(),
+            ..LogEntry::new(Level::Error, "panic", "Index out of bounds".to_owned())
+        };
+        for _ in 0..2 {
+            logger.log_complete_entry(&panic);
+        }
+        logger.flush();
+
+        let lines = memory.lines();
+        assert_eq!(2, lines.len(), "{:?}", lines);
+        assert!(lines
+            .iter()
+            .all(|line| line.contains("Index out of bounds") && line.contains("billing.rs:12")));
+    }
+
+    #[test]
+    fn test_log_duplicate_summary_when_window_expires() {
+        let memory = MemorySink::default();
+        let logger = Logger::<NoBacktraceFilter, AllLogFilter>::default()
+            .with_format(LogFormat::Logfmt)
+            .with_duplicate_suppression(DuplicateConfig::new(std::time::Duration::from_millis(50)))
+            .with_sink(LogSink::Memory(memory.clone()));
+
+        for _ in 0..3 {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("Connection lost"))
+                    .level(Level::Error)
+                    .target("services::database")
+                    .build(),
+            );
+        }
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        // Written by the background thread, without any flush or other message.
+        let lines = memory.lines();
+        assert_eq!(2, lines.len(), "{:?}", lines);
+        assert!(lines[1].contains(" msg=\"previous message repeated 2 times\""));
+    }
+
+    #[test]
+    fn test_async_writer_before_sink() {
+        let memory = MemorySink::default();
+        let logger = Logger::<NoBacktraceFilter, AllLogFilter>::default()
+            .with_async_writer(AsyncWriterConfig::default())
+            .with_sink(LogSink::Memory(memory.clone()));
+        assert!(matches!(logger.sink, LogSink::Async(_)));
+
+        logger.log(
+            &Record::builder()
+                .args(format_args!("Written later"))
+                .level(Level::Info)
+                .build(),
+        );
+        logger.flush();
+        assert_eq!(1, memory.lines().len());
+    }
 }
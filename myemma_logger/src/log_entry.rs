@@ -0,0 +1,59 @@
+use crate::log_fields::{collect_fields, HTTP_REQUEST_FIELD};
+use crate::TraceContext;
+use chrono::{DateTime, Utc};
+use log::{Level, Record};
+use serde_json::{Map, Value};
+
+/// A single log message with all its information, independent of the `LogFormat`.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub level: Level,
+    /// Unrecoverable error (panic), logged with a higher severity than `Level::Error`.
+    pub critical: bool,
+    pub target: String,
+    pub message: String,
+    /// Backtrace from `BacktraceFilter::get_backtrace_info`, empty when not captured.
+    pub backtrace: String,
+    /// Structured key/value fields.
+    pub fields: Map<String, Value>,
+    /// The `http_request` field of an access log entry.
+    pub http_request: Option<Value>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub module_path: Option<String>,
+    pub trace_context: Option<TraceContext>,
+    pub time: DateTime<Utc>,
+}
+
+impl LogEntry {
+    pub fn new(level: Level, target: &str, message: String) -> Self {
+        Self {
+            level,
+            critical: false,
+            target: target.to_owned(),
+            message,
+            backtrace: String::new(),
+            fields: Map::new(),
+            http_request: None,
+            file: None,
+            line: None,
+            module_path: None,
+            trace_context: TraceContext::current(),
+            time: Utc::now(),
+        }
+    }
+
+    /// The backtrace is not captured, that depends on the `BacktracePolicy`.
+    pub fn from_record(record: &Record) -> Self {
+        let mut fields = collect_fields(record);
+        let http_request = fields.remove(HTTP_REQUEST_FIELD);
+        Self {
+            fields,
+            http_request,
+            file: record.file().map(str::to_owned),
+            line: record.line(),
+            module_path: record.module_path().map(str::to_owned),
+            ..Self::new(record.level(), record.target(), record.args().to_string())
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use log::kv::{self, Key, VisitSource};
+use log::Record;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Structured field with the request of an access log entry, in the format of the
+/// `httpRequest` of a Google Cloud log entry:
+/// https://cloud.google.com/logging/docs/reference/v2/rest/v2/LogEntry#HttpRequest
+pub const HTTP_REQUEST_FIELD: &str = "http_request";
+
+/// Collect the structured key/value fields of the record,
+/// for example the fields of `log::info!(user_id = 5; "Logged in")`.
+pub(crate) fn collect_fields(record: &Record) -> Map<String, Value> {
+    let mut collector = FieldCollector(Map::new());
+    // Collecting never fails, so the error can be ignored.
+    let _ = record.key_values().visit(&mut collector);
+    collector.0
+}
+
+struct FieldCollector(Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for FieldCollector {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value =
+            serde_json::to_value(&value).unwrap_or_else(|_| Value::String(value.to_string()));
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// Split the fields in labels (only string values) and the remaining fields.
+pub(crate) fn split_label_fields(
+    fields: &Map<String, Value>,
+    label_fields: &[String],
+) -> (HashMap<String, String>, Map<String, Value>) {
+    let mut labels = HashMap::new();
+    let mut other_fields = Map::new();
+    for (key, value) in fields {
+        if label_fields.contains(key) {
+            let label = match value {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            labels.insert(key.clone(), label);
+        } else {
+            other_fields.insert(key.clone(), value.clone());
+        }
+    }
+    (labels, other_fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct User {
+        id: u32,
+        roles: Vec<&'static str>,
+    }
+
+    #[test]
+    fn test_collect_fields() {
+        let user = User {
+            id: 5,
+            roles: vec!["admin"],
+        };
+        let user_value = kv::Value::from_serde(&user);
+        let key_values = [
+            ("name", kv::Value::from("Jane Doe")),
+            ("retry", kv::Value::from(2)),
+            ("user", user_value),
+        ];
+        let record = Record::builder()
+            .args(format_args!("Logged in"))
+            .key_values(&key_values)
+            .build();
+
+        let fields = collect_fields(&record);
+        assert_eq!(
+            serde_json::json!({"user": {"id": 5, "roles": ["admin"]}, "name": "Jane Doe", "retry": 2}),
+            Value::Object(fields.clone())
+        );
+        assert_eq!(
+            " name=\"Jane Doe\" retry=2 user={\"id\":5,\"roles\":[\"admin\"]}",
+            crate::format_text::format_text_fields(&fields)
+        );
+    }
+}
@@ -1,7 +1,13 @@
-use log::Metadata;
+use log::{LevelFilter, Metadata};
 
 pub trait LogFilter {
     /// Filter out all log message that you do or don't want logged.
     /// Only the items that return `true` will be kept.
     fn filter(&self, metadata: &Metadata) -> bool;
+
+    /// The most verbose level this filter can keep, used for `log::set_max_level`
+    /// so disabled messages are skipped early.
+    fn max_level(&self) -> LevelFilter {
+        LevelFilter::Trace
+    }
 }
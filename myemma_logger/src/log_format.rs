@@ -1,16 +1,231 @@
-#[derive(Debug, Default)]
+use crate::format_ecs::format_ecs;
+use crate::format_gcp::format_gcp;
+use crate::format_logfmt::format_logfmt;
+use crate::format_otel::format_otel;
+use crate::format_text::{format_text, TextOptions};
+use crate::{LogEntry, LogSink};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum LogFormat {
     #[default]
     Text,
+    /// Google Cloud structured logging.
     Json,
+    /// `key=value` pairs.
+    Logfmt,
+    /// Elastic Common Schema JSON.
+    Ecs,
+    /// OpenTelemetry log data model as JSON.
+    OpenTelemetry,
+}
+
+/// The `LOG_FORMAT` environment variable contains an unknown format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLogFormat {
+    pub format: String,
+}
+
+impl Display for UnknownLogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unknown log format `{}`, expected one of: text, json, logfmt, ecs, otel",
+            self.format
+        )
+    }
+}
+
+impl std::error::Error for UnknownLogFormat {}
+
+/// The error of the last `LogFormat::get_format` with an unknown format,
+/// it is logged once the logger is installed.
+static UNKNOWN_FORMAT: Mutex<Option<UnknownLogFormat>> = Mutex::new(None);
+
+/// Log the error of an unknown `LOG_FORMAT`, only the first time.
+pub(crate) fn log_unknown_format() {
+    let error = UNKNOWN_FORMAT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some(error) = error {
+        log::warn!("{}, using `text` instead.", error);
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = UnknownLogFormat;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            "logfmt" => Ok(LogFormat::Logfmt),
+            "ecs" => Ok(LogFormat::Ecs),
+            "otel" | "opentelemetry" => Ok(LogFormat::OpenTelemetry),
+            _ => Err(UnknownLogFormat {
+                format: format.to_owned(),
+            }),
+        }
+    }
 }
 
 impl LogFormat {
+    /// Get the format from the `LOG_FORMAT` environment variable.
+    /// An unknown format is replaced by `Text` and logged as a warning when the logger
+    /// is installed.
     pub fn get_format() -> Self {
-        let log_format = std::env::var("LOG_FORMAT").unwrap_or_else(|_| "text".to_owned());
-        match log_format.as_ref() {
-            "json" => LogFormat::Json,
-            _ => LogFormat::Text,
+        Self::try_get_format().unwrap_or_else(|err| {
+            *UNKNOWN_FORMAT
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(err);
+            LogFormat::Text
+        })
+    }
+
+    /// Get the format from the `LOG_FORMAT` environment variable, defaults to `Text`.
+    pub fn try_get_format() -> Result<Self, UnknownLogFormat> {
+        match std::env::var("LOG_FORMAT") {
+            Ok(log_format) => log_format.parse(),
+            Err(_) => Ok(LogFormat::Text),
         }
     }
+
+    /// Format the entry to a single line, or multiple lines for Text with a backtrace.
+    pub fn format_entry(&self, entry: &LogEntry, options: &FormatOptions) -> String {
+        match self {
+            LogFormat::Text => format_text(entry, &options.text, options.colored),
+            LogFormat::Json => format_gcp(entry, options),
+            LogFormat::Logfmt => format_logfmt(entry, options),
+            LogFormat::Ecs => format_ecs(entry, options),
+            LogFormat::OpenTelemetry => format_otel(entry, options),
+        }
+    }
+}
+
+/// Information about the service that is added to every log entry.
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Operation id in Json, service id in the other formats.
+    pub id: String,
+    /// Operation producer in Json, service name in the other formats.
+    pub producer: String,
+    /// Structured fields with these keys are added as labels instead of payload fields.
+    pub label_fields: Vec<String>,
+    /// The Google Cloud project of the traces, used to link log entries to a trace.
+    pub trace_project: Option<String>,
+    pub text: TextOptions,
+    /// `ColorMode` of the Text format resolved for the sink.
+    colored: bool,
+}
+
+impl FormatOptions {
+    /// The trace project defaults to the `GOOGLE_CLOUD_PROJECT` environment variable.
+    pub fn new(id: &str, producer: &str) -> Self {
+        Self {
+            id: id.to_owned(),
+            producer: producer.to_owned(),
+            label_fields: Vec::new(),
+            trace_project: std::env::var("GOOGLE_CLOUD_PROJECT").ok(),
+            text: TextOptions::default(),
+            colored: false,
+        }
+        .with_colors_for(&LogSink::Stdout)
+    }
+
+    /// Decide if the Text format uses colors for lines written to `sink`.
+    /// Call this again after the `ColorMode` or the sink is changed.
+    pub fn with_colors_for(mut self, sink: &LogSink) -> Self {
+        self.colored = self.text.color.enabled_for(sink);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+    use serde_json::{json, Value};
+
+    fn entry() -> LogEntry {
+        let mut entry = LogEntry::new(
+            Level::Warn,
+            "services::billing",
+            "Invoice failed".to_owned(),
+        );
+        entry.fields.insert("user_id".to_owned(), json!("5"));
+        entry
+            .fields
+            .insert("reason".to_owned(), json!("card declined"));
+        entry.file = Some("src/billing.rs".to_owned());
+        entry.line = Some(12);
+        entry.backtrace = "\n   billing::invoice at src/billing.rs:12:5".to_owned();
+        entry
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(Ok(LogFormat::Logfmt), "logfmt".parse());
+        assert_eq!(Ok(LogFormat::OpenTelemetry), "otel".parse());
+        assert_eq!(
+            Err(UnknownLogFormat {
+                format: "xml".to_owned()
+            }),
+            "xml".parse::<LogFormat>()
+        );
+    }
+
+    #[test]
+    fn test_format_logfmt() {
+        let line = LogFormat::Logfmt.format_entry(&entry(), &FormatOptions::new("id", "backend"));
+        assert!(line.contains(" level=warn target=services::billing msg=\"Invoice failed\""));
+        assert!(line.contains(" reason=\"card declined\" user_id=5 "));
+        assert!(line.ends_with(" backtrace=\"billing::invoice at src/billing.rs:12:5\""));
+    }
+
+    #[test]
+    fn test_format_ecs() {
+        let mut options = FormatOptions::new("id", "backend");
+        options.label_fields = vec!["user_id".to_owned()];
+        let line = LogFormat::Ecs.format_entry(&entry(), &options);
+        let log_entry: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!("warn", log_entry["log.level"]);
+        assert_eq!("Invoice failed", log_entry["message"]);
+        assert_eq!("backend", log_entry["service.name"]);
+        assert_eq!(12, log_entry["log.origin.file.line"]);
+        assert_eq!("5", log_entry["labels"]["user_id"]);
+        assert_eq!("card declined", log_entry["reason"]);
+        assert_eq!(
+            "billing::invoice at src/billing.rs:12:5",
+            log_entry["error.stack_trace"]
+        );
+    }
+
+    #[test]
+    fn test_format_gcp_stack_trace() {
+        let line = LogFormat::Json.format_entry(&entry(), &FormatOptions::new("id", "backend"));
+        let log_entry: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!("Invoice failed", log_entry["message"]);
+        assert_eq!(
+            "Invoice failed\n    at billing::invoice (src/billing.rs:12:5)",
+            log_entry["stack_trace"]
+        );
+    }
+
+    #[test]
+    fn test_format_open_telemetry() {
+        let mut entry = entry();
+        entry.critical = true;
+        let line =
+            LogFormat::OpenTelemetry.format_entry(&entry, &FormatOptions::new("id", "backend"));
+        let log_record: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!("FATAL", log_record["SeverityText"]);
+        assert_eq!(21, log_record["SeverityNumber"]);
+        assert_eq!("Invoice failed", log_record["Body"]);
+        assert_eq!("backend", log_record["Resource"]["service.name"]);
+        assert_eq!("card declined", log_record["Attributes"]["reason"]);
+        assert_eq!(12, log_record["Attributes"]["code.lineno"]);
+    }
 }
@@ -0,0 +1,205 @@
+use crate::LogEntry;
+use log::Level;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Keeps a fraction of the log entries per level and target.
+///
+/// Entries with a `TraceContext` are sampled by trace id with a single trace ratio,
+/// so either all or none of the sampled entries of a request are kept, whatever their level.
+/// The trace ratio defaults to the lowest configured ratio, see `with_trace_ratio`.
+/// Levels and targets that are not sampled (ratio 1.0) are always kept.
+/// `Error` entries are never sampled away.
+#[derive(Debug, Default)]
+pub struct LogSampler {
+    level_ratios: HashMap<Level, f64>,
+    /// Sorted by the length of the target, longest first.
+    target_ratios: Vec<(String, Level, f64)>,
+    trace_ratio: Option<f64>,
+    /// Source of the random numbers of entries without trace.
+    counter: AtomicU64,
+}
+
+impl LogSampler {
+    /// Keep all entries, change it with `with_level_ratio` and `with_target_ratio`.
+    pub fn new() -> Self {
+        Self {
+            counter: AtomicU64::new(seed()),
+            ..Default::default()
+        }
+    }
+
+    /// Keep `ratio` (0.0 to 1.0) of the entries of `level`.
+    pub fn with_level_ratio(mut self, level: Level, ratio: f64) -> Self {
+        self.level_ratios.insert(level, ratio.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Keep `ratio` (0.0 to 1.0) of the entries of `level` for `target` and its sub modules,
+    /// this overrides the ratio of the level.
+    pub fn with_target_ratio(mut self, target: &str, level: Level, ratio: f64) -> Self {
+        self.target_ratios
+            .retain(|(existing, existing_level, _)| existing != target || *existing_level != level);
+        self.target_ratios
+            .push((target.to_owned(), level, ratio.clamp(0.0, 1.0)));
+        self.target_ratios
+            .sort_by_key(|(target, _, _)| std::cmp::Reverse(target.len()));
+        self
+    }
+
+    /// Keep `ratio` (0.0 to 1.0) of the traces, used for all sampled entries with a trace.
+    pub fn with_trace_ratio(mut self, ratio: f64) -> Self {
+        self.trace_ratio = Some(ratio.clamp(0.0, 1.0));
+        self
+    }
+
+    fn trace_ratio(&self) -> f64 {
+        self.trace_ratio.unwrap_or_else(|| {
+            self.level_ratios
+                .values()
+                .chain(self.target_ratios.iter().map(|(_, _, ratio)| ratio))
+                .copied()
+                .fold(1.0, f64::min)
+        })
+    }
+
+    fn ratio(&self, level: Level, target: &str) -> f64 {
+        self.target_ratios
+            .iter()
+            .find(|(directive_target, directive_level, _)| {
+                *directive_level == level
+                    && target
+                        .strip_prefix(directive_target.as_str())
+                        .map(|rest| rest.is_empty() || rest.starts_with("::"))
+                        .unwrap_or_default()
+            })
+            .map(|(_, _, ratio)| *ratio)
+            .or_else(|| self.level_ratios.get(&level).copied())
+            .unwrap_or(1.0)
+    }
+
+    /// Whether the entry is kept.
+    pub fn keep(&self, entry: &LogEntry) -> bool {
+        if entry.level == Level::Error || entry.critical {
+            return true;
+        }
+        let ratio = self.ratio(entry.level, &entry.target);
+        if ratio >= 1.0 {
+            return true;
+        }
+        match &entry.trace_context {
+            Some(trace_context) => {
+                to_fraction(trace_sample(&trace_context.trace_id)) < self.trace_ratio()
+            }
+            None => to_fraction(splitmix64(self.counter.fetch_add(1, Ordering::Relaxed))) < ratio,
+        }
+    }
+}
+
+/// Trace ids are random, so the last 16 hex characters are used as is.
+/// Other ids are hashed with FNV-1a, so every service makes the same decision.
+fn trace_sample(trace_id: &str) -> u64 {
+    let start = trace_id.len().saturating_sub(16);
+    trace_id
+        .get(start..)
+        .and_then(|tail| u64::from_str_radix(tail, 16).ok())
+        .unwrap_or_else(|| {
+            trace_id.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+            })
+        })
+}
+
+fn to_fraction(sample: u64) -> f64 {
+    (sample >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// More info see: https://prng.di.unimi.it/splitmix64.c
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TraceContext;
+
+    fn entry(level: Level, target: &str, trace_id: Option<&str>) -> LogEntry {
+        let mut entry = LogEntry::new(level, target, "Message".to_owned());
+        entry.trace_context = trace_id.map(|trace_id| TraceContext {
+            trace_id: trace_id.to_owned(),
+            span_id: None,
+            sampled: true,
+        });
+        entry
+    }
+
+    #[test]
+    fn test_errors_are_never_sampled() {
+        let sampler = LogSampler::new()
+            .with_level_ratio(Level::Error, 0.0)
+            .with_level_ratio(Level::Debug, 0.0);
+        assert!(sampler.keep(&entry(Level::Error, "main", None)));
+        assert!(!sampler.keep(&entry(Level::Debug, "main", None)));
+        assert!(sampler.keep(&entry(Level::Info, "main", None)));
+    }
+
+    #[test]
+    fn test_target_ratio() {
+        let sampler = LogSampler::new()
+            .with_level_ratio(Level::Debug, 0.0)
+            .with_target_ratio("services::billing", Level::Debug, 1.0);
+        assert!(sampler.keep(&entry(Level::Debug, "services::billing::invoice", None)));
+        assert!(!sampler.keep(&entry(Level::Debug, "services::users", None)));
+    }
+
+    #[test]
+    fn test_sample_whole_trace() {
+        let sampler = LogSampler::new()
+            .with_level_ratio(Level::Debug, 0.5)
+            .with_level_ratio(Level::Info, 0.5);
+        let kept = "4bf92f3577b34da643ce929d0e0e4736";
+        let dropped = "4bf92f3577b34da6f3ce929d0e0e4736";
+        for _ in 0..10 {
+            assert!(sampler.keep(&entry(Level::Debug, "main", Some(kept))));
+            assert!(sampler.keep(&entry(Level::Info, "rocket", Some(kept))));
+            assert!(!sampler.keep(&entry(Level::Debug, "main", Some(dropped))));
+            assert!(!sampler.keep(&entry(Level::Info, "rocket", Some(dropped))));
+        }
+    }
+
+    #[test]
+    fn test_same_decision_for_all_levels_of_trace() {
+        let sampler = LogSampler::new()
+            .with_level_ratio(Level::Debug, 0.1)
+            .with_level_ratio(Level::Info, 0.9);
+        // Kept with 0.9, but not with the trace ratio of 0.1.
+        let trace_id = "4bf92f3577b34da68f3ce929d0e0e473";
+        assert!(!sampler.keep(&entry(Level::Debug, "main", Some(trace_id))));
+        assert!(!sampler.keep(&entry(Level::Info, "main", Some(trace_id))));
+        assert!(sampler.keep(&entry(Level::Warn, "main", Some(trace_id))));
+
+        let sampler = sampler.with_trace_ratio(0.9);
+        assert!(sampler.keep(&entry(Level::Debug, "main", Some(trace_id))));
+        assert!(sampler.keep(&entry(Level::Info, "main", Some(trace_id))));
+    }
+
+    #[test]
+    fn test_ratio_without_trace() {
+        let sampler = LogSampler::new().with_level_ratio(Level::Debug, 0.25);
+        let kept = (0..10_000)
+            .filter(|_| sampler.keep(&entry(Level::Debug, "main", None)))
+            .count();
+        assert!((2_000..3_000).contains(&kept), "Kept {} entries", kept);
+    }
+}
@@ -0,0 +1,246 @@
+#[cfg(unix)]
+use crate::SYSLOG_SOCKET;
+use crate::{
+    AsyncWriter, FormatOptions, JournaldSink, LogEntry, RotatingFile, RotatingFileConfig,
+    SyslogSink,
+};
+use log::Level;
+use std::fs::{File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::net::ToSocketAddrs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// The destination of the formatted log lines.
+///
+/// Sinks are cheap to clone, clones write to the same destination.
+#[derive(Clone, Default)]
+pub enum LogSink {
+    /// Write all lines to stdout.
+    #[default]
+    Stdout,
+    /// Write all lines to stderr.
+    Stderr,
+    /// Write `Error` lines to stderr and all other lines to stdout.
+    SplitErrors,
+    /// Append all lines to a file.
+    File(Arc<Mutex<File>>),
+    /// Append all lines to a file that is rotated by size or date.
+    RotatingFile(RotatingFile),
+    /// Write all lines to any writer.
+    Writer(Arc<Mutex<Box<dyn Write + Send>>>),
+    /// Keep all lines in memory, useful for tests.
+    Memory(MemorySink),
+    /// Write the lines to another sink on a background thread.
+    Async(AsyncWriter),
+    /// Send all lines as RFC 5424 syslog messages.
+    Syslog(SyslogSink),
+    /// Send all lines to journald.
+    Journald(JournaldSink),
+}
+
+impl std::fmt::Debug for LogSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogSink::Stdout => write!(f, "Stdout"),
+            LogSink::Stderr => write!(f, "Stderr"),
+            LogSink::SplitErrors => write!(f, "SplitErrors"),
+            LogSink::File(_) => write!(f, "File"),
+            LogSink::RotatingFile(file) => f.debug_tuple("RotatingFile").field(file).finish(),
+            LogSink::Writer(_) => write!(f, "Writer"),
+            LogSink::Memory(memory) => f.debug_tuple("Memory").field(memory).finish(),
+            LogSink::Async(writer) => f.debug_tuple("Async").field(writer).finish(),
+            LogSink::Syslog(syslog) => f.debug_tuple("Syslog").field(syslog).finish(),
+            LogSink::Journald(journald) => f.debug_tuple("Journald").field(journald).finish(),
+        }
+    }
+}
+
+impl LogSink {
+    /// Open (or create) the file at `path` and append all lines to it.
+    pub fn file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(LogSink::File(Arc::new(Mutex::new(file))))
+    }
+
+    /// Open (or create) the file at `path` and rotate it as configured.
+    pub fn rotating_file<P: AsRef<Path>>(path: P, config: RotatingFileConfig) -> io::Result<Self> {
+        Ok(LogSink::RotatingFile(RotatingFile::new(path, config)?))
+    }
+
+    /// Write all lines to `writer`.
+    pub fn writer<W: Write + Send + 'static>(writer: W) -> Self {
+        LogSink::Writer(Arc::new(Mutex::new(Box::new(writer))))
+    }
+
+    /// Send all lines to a syslog server over UDP.
+    pub fn syslog_udp<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(LogSink::Syslog(SyslogSink::udp(addr)?))
+    }
+
+    /// Send all lines to the local syslog daemon at `SYSLOG_SOCKET`.
+    #[cfg(unix)]
+    pub fn syslog_unix() -> io::Result<Self> {
+        Ok(LogSink::Syslog(SyslogSink::unix(SYSLOG_SOCKET)?))
+    }
+
+    /// Send all lines to journald at `JOURNALD_SOCKET`.
+    pub fn journald() -> io::Result<Self> {
+        Ok(LogSink::Journald(JournaldSink::new()?))
+    }
+
+    /// Whether the lines end up in a terminal, used to decide on colors.
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            LogSink::Stdout => io::stdout().is_terminal(),
+            LogSink::Stderr => io::stderr().is_terminal(),
+            LogSink::SplitErrors => io::stdout().is_terminal() && io::stderr().is_terminal(),
+            LogSink::Async(writer) => writer.sink().is_terminal(),
+            LogSink::File(_)
+            | LogSink::RotatingFile(_)
+            | LogSink::Writer(_)
+            | LogSink::Memory(_)
+            | LogSink::Syslog(_)
+            | LogSink::Journald(_) => false,
+        }
+    }
+
+    /// Write a single formatted line, a newline is added at the end.
+    /// Errors during writing are ignored, logging should never stop the application.
+    pub fn write_line(&self, level: Level, line: &str) {
+        match self {
+            LogSink::Stdout => {
+                let _ = writeln!(io::stdout().lock(), "{}", line);
+            }
+            LogSink::Stderr => {
+                let _ = writeln!(io::stderr().lock(), "{}", line);
+            }
+            LogSink::SplitErrors => {
+                if level == Level::Error {
+                    let _ = writeln!(io::stderr().lock(), "{}", line);
+                } else {
+                    let _ = writeln!(io::stdout().lock(), "{}", line);
+                }
+            }
+            LogSink::File(file) => {
+                let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+                let _ = writeln!(file, "{}", line);
+            }
+            LogSink::RotatingFile(file) => {
+                let _ = file.write_line(line);
+            }
+            LogSink::Writer(writer) => {
+                let mut writer = writer.lock().unwrap_or_else(PoisonError::into_inner);
+                let _ = writeln!(writer, "{}", line);
+            }
+            LogSink::Memory(memory) => memory.push(line),
+            LogSink::Async(writer) => writer.write_line(level, line),
+            LogSink::Syslog(syslog) => syslog.send_line(level, line),
+            LogSink::Journald(journald) => journald.send_line(level, line),
+        }
+    }
+
+    /// Write the formatted line of an entry.
+    /// Syslog and journald (also behind `Async`) get the severity, source location, id
+    /// and producer as well, with `write_line` they only get the level.
+    pub fn write_entry(&self, entry: &LogEntry, line: &str, options: &FormatOptions) {
+        match self {
+            LogSink::Syslog(syslog) => syslog.send_entry(entry, line, options),
+            LogSink::Journald(journald) => journald.send_entry(entry, line, options),
+            LogSink::Async(writer) => writer.write_entry(entry, line, options),
+            _ => self.write_line(entry.level, line),
+        }
+    }
+
+    /// Whether `write_entry` sends more than the formatted line.
+    pub(crate) fn takes_entries(&self) -> bool {
+        match self {
+            LogSink::Syslog(_) | LogSink::Journald(_) => true,
+            LogSink::Async(writer) => writer.sink().takes_entries(),
+            _ => false,
+        }
+    }
+
+    /// Flush all buffered lines to the destination.
+    /// For `Async` this waits until all queued lines are written.
+    pub fn flush(&self) {
+        match self {
+            LogSink::Stdout => {
+                let _ = io::stdout().flush();
+            }
+            LogSink::Stderr => {
+                let _ = io::stderr().flush();
+            }
+            LogSink::SplitErrors => {
+                let _ = io::stdout().flush();
+                let _ = io::stderr().flush();
+            }
+            LogSink::File(file) => {
+                let _ = file.lock().unwrap_or_else(PoisonError::into_inner).flush();
+            }
+            LogSink::RotatingFile(file) => {
+                let _ = file.flush();
+            }
+            LogSink::Writer(writer) => {
+                let _ = writer
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .flush();
+            }
+            LogSink::Memory(_) | LogSink::Syslog(_) | LogSink::Journald(_) => {}
+            LogSink::Async(writer) => writer.flush(),
+        }
+    }
+}
+
+/// Flushes the sink when dropped, keep it alive until the end of `main`
+/// so no log lines are lost at shutdown.
+#[derive(Debug)]
+#[must_use = "The sink is flushed when the guard is dropped"]
+pub struct FlushGuard {
+    sink: LogSink,
+}
+
+impl FlushGuard {
+    pub fn new(sink: LogSink) -> Self {
+        Self { sink }
+    }
+}
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        self.sink.flush();
+    }
+}
+
+/// Keeps all written lines in memory.
+/// Clones share the same lines, so keep a clone to inspect what was logged.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    lines: Arc<Mutex<Vec<String>>>,
+}
+
+impl MemorySink {
+    fn push(&self, line: &str) {
+        self.lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(line.to_owned());
+    }
+
+    /// Get all lines written so far.
+    pub fn lines(&self) -> Vec<String> {
+        self.lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Remove all lines written so far.
+    pub fn clear(&self) {
+        self.lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
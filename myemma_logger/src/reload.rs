@@ -0,0 +1,147 @@
+use crate::{DirectiveError, DirectiveFilter, LogFilter};
+use log::{LevelFilter, Metadata};
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// `LogFilter` that can be replaced at runtime with its `ReloadHandle`.
+#[derive(Debug, Clone, Default)]
+pub struct ReloadableFilter {
+    filter: Arc<RwLock<DirectiveFilter>>,
+}
+
+impl ReloadableFilter {
+    pub fn new(filter: DirectiveFilter) -> Self {
+        Self {
+            filter: Arc::new(RwLock::new(filter)),
+        }
+    }
+
+    /// Get the handle to change the filter, also after the logger is registered.
+    pub fn handle(&self) -> ReloadHandle {
+        ReloadHandle {
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl LogFilter for ReloadableFilter {
+    fn filter(&self, metadata: &Metadata) -> bool {
+        self.filter
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .filter(metadata)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.filter
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .max_level()
+    }
+}
+
+/// Where the directives are read from when reloading.
+#[derive(Debug, Clone)]
+pub enum ReloadSource {
+    /// Environment variable, only useful when the process changes it itself.
+    Env(String),
+    /// File that contains the directives.
+    File(PathBuf),
+}
+
+impl ReloadSource {
+    fn read(&self) -> Result<String, String> {
+        match self {
+            ReloadSource::Env(var) => {
+                std::env::var(var).map_err(|err| format!("Could not read `{}`: {}", var, err))
+            }
+            ReloadSource::File(path) => std::fs::read_to_string(path)
+                .map_err(|err| format!("Could not read `{}`: {}", path.display(), err)),
+        }
+    }
+}
+
+/// Changes the filter of a `ReloadableFilter`.
+#[derive(Debug, Clone)]
+pub struct ReloadHandle {
+    filter: Arc<RwLock<DirectiveFilter>>,
+}
+
+impl ReloadHandle {
+    /// The directives that are currently used.
+    pub fn current(&self) -> DirectiveFilter {
+        self.filter
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replace the filter with new directives and update `log::set_max_level`,
+    /// to the max level of all destinations when a `FanOutLogger` is installed.
+    /// The change is logged, the filter is unchanged when the directives are invalid.
+    pub fn reload(&self, directives: &str) -> Result<(), DirectiveError> {
+        let new_filter: DirectiveFilter = directives.parse()?;
+        let old_filter = std::mem::replace(
+            &mut *self.filter.write().unwrap_or_else(PoisonError::into_inner),
+            new_filter.clone(),
+        );
+        log::set_max_level(
+            crate::install::installed_max_level().unwrap_or_else(|| new_filter.max_level()),
+        );
+        // Logged after the swap, so concurrent reloads are logged in the order they are applied.
+        log::warn!(
+            "Changed log directives from `{}` to `{}`",
+            old_filter,
+            new_filter
+        );
+        Ok(())
+    }
+
+    /// Reload the directives from `source`.
+    pub fn reload_from(&self, source: &ReloadSource) -> Result<(), String> {
+        let directives = source.read()?;
+        self.reload(directives.trim())
+            .map_err(|err| err.to_string())
+    }
+
+    /// Start a thread that reloads the directives from `source` every time the process
+    /// receives `SIGHUP`.
+    #[cfg(all(unix, feature = "signal"))]
+    pub fn reload_on_sighup(&self, source: ReloadSource) -> std::io::Result<()> {
+        let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGHUP])?;
+        let handle = self.clone();
+        std::thread::Builder::new()
+            .name("myemma-logger-reload".to_owned())
+            .spawn(move || {
+                for _ in signals.forever() {
+                    if let Err(err) = handle.reload_from(&source) {
+                        log::error!("Could not reload log directives: {}", err);
+                    }
+                }
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn test_reload_filter() {
+        let filter = ReloadableFilter::new("info".parse().unwrap());
+        let handle = filter.handle();
+        let debug = Metadata::builder()
+            .level(Level::Debug)
+            .target("services")
+            .build();
+        assert!(!filter.filter(&debug));
+
+        handle.reload("info,services=debug").unwrap();
+        assert!(filter.filter(&debug));
+
+        assert!(handle.reload("services=loud").is_err());
+        assert_eq!("info,services=debug", handle.current().to_string());
+    }
+}
@@ -0,0 +1,394 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+
+/// When the current log file is rotated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// Rotate before the file would become larger than this amount of bytes.
+    Size(u64),
+    /// Rotate at the first line of a new day (UTC).
+    Daily,
+}
+
+#[derive(Debug, Clone)]
+pub struct RotatingFileConfig {
+    pub rotation: Rotation,
+    /// Amount of rotated files that are kept next to the current file.
+    pub max_files: usize,
+    /// Compress rotated files with gzip, requires the `gzip` feature.
+    pub compress: bool,
+}
+
+impl Default for RotatingFileConfig {
+    fn default() -> Self {
+        Self {
+            rotation: Rotation::Size(10 * 1024 * 1024),
+            max_files: 5,
+            compress: false,
+        }
+    }
+}
+
+/// Appends lines to a file and rotates it to `<path>.1`, `<path>.2`, ...
+/// (`<path>.1.gz` when compressed), the oldest files are removed.
+///
+/// Every line is written at once, so a line is never split over two files.
+/// Clones write to the same file.
+#[derive(Clone)]
+pub struct RotatingFile {
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    path: PathBuf,
+    config: RotatingFileConfig,
+    file: File,
+    size: u64,
+    date: NaiveDate,
+    /// Compression of the last rotated file.
+    compression: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl std::fmt::Debug for RotatingFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        f.debug_struct("RotatingFile")
+            .field("path", &state.path)
+            .field("config", &state.config)
+            .finish()
+    }
+}
+
+impl RotatingFile {
+    /// Open (or create) the file at `path`, an existing file is appended to.
+    pub fn new<P: AsRef<Path>>(path: P, config: RotatingFileConfig) -> io::Result<Self> {
+        if config.compress && cfg!(not(feature = "gzip")) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Compressing log files requires the `gzip` feature",
+            ));
+        }
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        // A file from a previous day is rotated at the first line.
+        let date = metadata
+            .modified()
+            .map(|modified| DateTime::<Utc>::from(modified).date_naive())
+            .unwrap_or_else(|_| Utc::now().date_naive());
+        Ok(Self {
+            state: Arc::new(Mutex::new(State {
+                path,
+                config,
+                file,
+                size: metadata.len(),
+                date,
+                compression: None,
+            })),
+        })
+    }
+
+    /// Write a single line, a newline is added at the end.
+    /// When rotating fails the line is written to the current file,
+    /// the next rotation is tried after the next size or period.
+    pub fn write_line(&self, line: &str) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let line = format!("{}\n", line);
+        if state.should_rotate(line.len() as u64) {
+            // Logging should continue, even when the old files can not be moved.
+            if state.rotate().is_err() {
+                state.size = 0;
+                state.date = Utc::now().date_naive();
+            }
+        }
+        state.file.write_all(line.as_bytes())?;
+        state.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Flush the current file and wait until the last rotated file is compressed.
+    pub fn flush(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.wait_for_compression()?;
+        state.file.flush()
+    }
+}
+
+impl State {
+    fn should_rotate(&self, line_length: u64) -> bool {
+        match self.config.rotation {
+            // An empty file is never rotated, so a single long line does not create empty files.
+            Rotation::Size(max_size) => self.size > 0 && self.size + line_length > max_size,
+            Rotation::Daily => self.size > 0 && self.date != Utc::now().date_naive(),
+        }
+    }
+
+    /// Path of the rotated file with `index`, 1 is the most recent.
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        if self.config.compress {
+            path.push(".gz");
+        }
+        PathBuf::from(path)
+    }
+
+    /// The current file is moved here first, so no rotated file is touched
+    /// before the current file is rotated successfully.
+    fn pending_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".rotating");
+        PathBuf::from(path)
+    }
+
+    /// Rotate the current file to the pending file, then move the pending file
+    /// in place of the rotated files.
+    ///
+    /// A pending file of a failed rotation is moved first, when that fails again
+    /// the current file is not rotated.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.wait_for_compression()?;
+        let pending = self.pending_path();
+        if pending.exists() {
+            self.shift_rotated_files()?;
+            self.finish_pending()?;
+            self.wait_for_compression()?;
+        }
+
+        self.file.flush()?;
+        fs::rename(&self.path, &pending)?;
+        match OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+        {
+            Ok(file) => self.file = file,
+            Err(err) => {
+                // `self.file` still writes to the pending file, move it back.
+                let _ = fs::rename(&pending, &self.path);
+                return Err(err);
+            }
+        }
+        self.size = 0;
+        self.date = Utc::now().date_naive();
+
+        self.shift_rotated_files()?;
+        self.finish_pending()
+    }
+
+    /// Remove the oldest rotated file and make room for the pending file.
+    fn shift_rotated_files(&self) -> io::Result<()> {
+        let max_files = self.config.max_files;
+        if max_files == 0 {
+            return Ok(());
+        }
+        remove_if_exists(&self.rotated_path(max_files))?;
+        for index in (1..max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rename the pending file to the first rotated file,
+    /// compressing is done on a background thread.
+    fn finish_pending(&mut self) -> io::Result<()> {
+        let pending = self.pending_path();
+        if self.config.max_files == 0 {
+            fs::remove_file(&pending)
+        } else if self.config.compress {
+            let rotated = self.rotated_path(1);
+            self.compression = Some(std::thread::spawn(move || {
+                compress(&pending, &rotated)?;
+                fs::remove_file(&pending)
+            }));
+            Ok(())
+        } else {
+            fs::rename(&pending, self.rotated_path(1))
+        }
+    }
+
+    fn wait_for_compression(&mut self) -> io::Result<()> {
+        match self.compression.take() {
+            Some(compression) => compression.join().unwrap_or_else(|_| {
+                Err(io::Error::other(
+                    "Compressing the rotated log file panicked",
+                ))
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        let _ = self.wait_for_compression();
+    }
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn compress(from: &Path, to: &Path) -> io::Result<()> {
+    let mut encoder =
+        flate2::write::GzEncoder::new(File::create(to)?, flate2::Compression::default());
+    io::copy(&mut File::open(from)?, &mut encoder)?;
+    encoder.finish()?.sync_all()
+}
+
+#[cfg(not(feature = "gzip"))]
+fn compress(_from: &Path, _to: &Path) -> io::Result<()> {
+    unreachable!("Checked in `RotatingFile::new`")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("myemma_logger_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_rotate_by_size_keeps_max_files() {
+        let dir = test_dir("size");
+        let path = dir.join("app.log");
+        let file = RotatingFile::new(
+            &path,
+            RotatingFileConfig {
+                rotation: Rotation::Size(25),
+                max_files: 2,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        for index in 0..8 {
+            file.write_line(&format!("{{\"line\":{}}}", index)).unwrap();
+        }
+        file.flush().unwrap();
+
+        // Every file holds 2 complete lines of 11 bytes.
+        assert_eq!(
+            "{\"line\":6}\n{\"line\":7}\n",
+            fs::read_to_string(&path).unwrap()
+        );
+        assert_eq!(
+            "{\"line\":4}\n{\"line\":5}\n",
+            fs::read_to_string(dir.join("app.log.1")).unwrap()
+        );
+        assert!(dir.join("app.log.2").exists());
+        assert!(!dir.join("app.log.3").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_long_line_is_not_split() {
+        let dir = test_dir("long");
+        let path = dir.join("app.log");
+        let file = RotatingFile::new(
+            &path,
+            RotatingFileConfig {
+                rotation: Rotation::Size(4),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        file.write_line("longer than the maximum size").unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(
+            "longer than the maximum size\n",
+            fs::read_to_string(&path).unwrap()
+        );
+        assert!(!dir.join("app.log.1").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_rotation_keeps_files() {
+        let dir = test_dir("failed");
+        let path = dir.join("app.log");
+        let file = RotatingFile::new(
+            &path,
+            RotatingFileConfig {
+                rotation: Rotation::Size(15),
+                max_files: 2,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        file.write_line("first").unwrap();
+        file.write_line("second").unwrap();
+        // A directory can not be replaced by a file, so moving `.1` to `.2` fails.
+        fs::create_dir(dir.join("app.log.2")).unwrap();
+        file.write_line("third").unwrap();
+        file.write_line("fourth").unwrap();
+        file.flush().unwrap();
+
+        // The current file is rotated, the pending file waits for the next rotation.
+        assert_eq!("third\nfourth\n", fs::read_to_string(&path).unwrap());
+        assert_eq!(
+            "first\nsecond\n",
+            fs::read_to_string(dir.join("app.log.rotating")).unwrap()
+        );
+        assert!(!dir.join("app.log.1").exists());
+
+        fs::remove_dir(dir.join("app.log.2")).unwrap();
+        file.write_line("fifth").unwrap();
+        assert_eq!("fifth\n", fs::read_to_string(&path).unwrap());
+        assert_eq!(
+            "third\nfourth\n",
+            fs::read_to_string(dir.join("app.log.1")).unwrap()
+        );
+        assert_eq!(
+            "first\nsecond\n",
+            fs::read_to_string(dir.join("app.log.2")).unwrap()
+        );
+        assert!(!dir.join("app.log.rotating").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_compress_rotated_files() {
+        use std::io::Read;
+
+        let dir = test_dir("gzip");
+        let path = dir.join("app.log");
+        let file = RotatingFile::new(
+            &path,
+            RotatingFileConfig {
+                rotation: Rotation::Size(10),
+                max_files: 1,
+                compress: true,
+            },
+        )
+        .unwrap();
+        file.write_line("first").unwrap();
+        file.write_line("second").unwrap();
+        file.flush().unwrap();
+
+        let mut decoder =
+            flate2::read::GzDecoder::new(File::open(dir.join("app.log.1.gz")).unwrap());
+        let mut rotated = String::new();
+        decoder.read_to_string(&mut rotated).unwrap();
+        assert_eq!("first\n", rotated);
+        assert!(!dir.join("app.log.rotating").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+use crate::{FormatOptions, LogEntry};
+use chrono::SecondsFormat;
+use log::Level;
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::Arc;
+
+/// Socket of the local syslog daemon.
+pub const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Default maximum size of a message in bytes, longer messages are truncated.
+/// Most syslog daemons accept 8 KiB.
+pub const DEFAULT_SYSLOG_MESSAGE_SIZE: usize = 8 * 1024;
+
+/// Id of the RFC 5424 structured data element with the source location and operation.
+/// `32473` is the private enterprise number reserved for documentation (RFC 5612).
+pub const SYSLOG_SD_ID: &str = "myemma@32473";
+
+/// The syslog facility of all messages of a `SyslogSink`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyslogFacility {
+    #[default]
+    User = 1,
+    Daemon = 3,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// The syslog severity of an entry, used by syslog and journald.
+/// Critical entries (panics) are `crit`, `Trace` and `Debug` are both `debug`.
+pub(crate) fn severity(level: Level, critical: bool) -> u8 {
+    if critical {
+        return 2;
+    }
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+#[derive(Debug)]
+enum Transport {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+}
+
+/// Sends every line as RFC 5424 syslog message over UDP or a Unix datagram socket.
+///
+/// The formatted line is the message, the source location, target, operation id and
+/// producer are added as structured data. The producer is also the app name.
+/// Clones send over the same socket.
+#[derive(Debug, Clone)]
+pub struct SyslogSink {
+    transport: Arc<Transport>,
+    facility: SyslogFacility,
+    hostname: String,
+    max_message_size: usize,
+}
+
+impl SyslogSink {
+    /// Send the messages to a remote syslog server.
+    pub fn udp<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "No syslog server address")
+        })?;
+        let local_addr = match addr {
+            SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+            SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+        };
+        let socket = UdpSocket::bind(local_addr)?;
+        socket.connect(addr)?;
+        Ok(Self::new(Transport::Udp(socket)))
+    }
+
+    /// Send the messages to a local syslog daemon, usually at `SYSLOG_SOCKET`.
+    #[cfg(unix)]
+    pub fn unix<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self::new(Transport::Unix(socket)))
+    }
+
+    fn new(transport: Transport) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            facility: SyslogFacility::default(),
+            hostname: hostname(),
+            max_message_size: DEFAULT_SYSLOG_MESSAGE_SIZE,
+        }
+    }
+
+    pub fn with_facility(mut self, facility: SyslogFacility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// Change the hostname, the hostname of the machine by default.
+    pub fn with_hostname(mut self, hostname: &str) -> Self {
+        self.hostname = hostname.to_owned();
+        self
+    }
+
+    /// Truncate longer messages, instead of the syslog daemon dropping them.
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    pub(crate) fn send_entry(&self, entry: &LogEntry, line: &str, options: &FormatOptions) {
+        let mut params = vec![
+            ("id", options.id.as_str()),
+            ("producer", options.producer.as_str()),
+            ("target", entry.target.as_str()),
+        ];
+        let line_number = entry.line.map(|line| line.to_string());
+        if let Some(file) = &entry.file {
+            params.push(("file", file));
+        }
+        if let Some(line_number) = &line_number {
+            params.push(("line", line_number));
+        }
+        if let Some(module_path) = &entry.module_path {
+            params.push(("module", module_path));
+        }
+        let structured_data = format!(
+            "[{}{}]",
+            SYSLOG_SD_ID,
+            params
+                .iter()
+                .map(|(name, value)| format!(" {}=\"{}\"", name, escape_param(value)))
+                .collect::<String>()
+        );
+        self.send(
+            severity(entry.level, entry.critical),
+            &entry.time.to_rfc3339_opts(SecondsFormat::Micros, true),
+            &options.producer,
+            &structured_data,
+            line,
+        );
+    }
+
+    /// Send a line without structured data, for example from an `AsyncWriter`.
+    pub(crate) fn send_line(&self, level: Level, line: &str) {
+        self.send(
+            severity(level, false),
+            &chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            "-",
+            "-",
+            line,
+        );
+    }
+
+    fn send(&self, severity: u8, time: &str, app_name: &str, structured_data: &str, line: &str) {
+        let mut message = format!(
+            "<{}>1 {} {} {} {} - {} {}",
+            self.facility as u8 * 8 + severity,
+            time,
+            header_field(&self.hostname, MAX_HOSTNAME_LENGTH),
+            header_field(app_name, MAX_APP_NAME_LENGTH),
+            std::process::id(),
+            structured_data,
+            line
+        );
+        if message.len() > self.max_message_size {
+            let mut end = self.max_message_size;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+        }
+        // Errors are ignored, just like the other sinks.
+        let _ = match &*self.transport {
+            Transport::Udp(socket) => socket.send(message.as_bytes()),
+            #[cfg(unix)]
+            Transport::Unix(socket) => socket.send(message.as_bytes()),
+        };
+    }
+}
+
+/// Maximum lengths of the header fields, from RFC 5424.
+const MAX_HOSTNAME_LENGTH: usize = 255;
+const MAX_APP_NAME_LENGTH: usize = 48;
+
+/// `HOSTNAME` is a shell variable, services do not have it,
+/// so the hostname is read from the kernel.
+fn hostname() -> String {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .chain(std::env::var("HOSTNAME").ok())
+        .map(|hostname| hostname.trim().to_owned())
+        .find(|hostname| !hostname.is_empty())
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+/// Header fields are printable ASCII without spaces, `-` when empty.
+fn header_field(value: &str, max_length: usize) -> String {
+    let field: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max_length)
+        .collect();
+    if field.is_empty() {
+        "-".to_owned()
+    } else {
+        field
+    }
+}
+
+fn escape_param(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(']', "\\]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsyncWriter, AsyncWriterConfig, LogSink};
+
+    #[test]
+    fn test_send_entry_over_udp() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sink = SyslogSink::udp(server.local_addr().unwrap())
+            .unwrap()
+            .with_facility(SyslogFacility::Local0)
+            .with_hostname("worker-1");
+        let mut entry = LogEntry::new(Level::Warn, "services::billing", "Retry".to_owned());
+        entry.file = Some("src/billing.rs".to_owned());
+        entry.line = Some(12);
+        entry.time = "2022-03-01T12:00:00.5Z".parse().unwrap();
+        let options = FormatOptions::new("op\"1]", "billing");
+        sink.send_entry(&entry, "Retry invoice_id=5", &options);
+
+        let mut buffer = [0; 1024];
+        let size = server.recv(&mut buffer).unwrap();
+        assert_eq!(
+            format!(
+                "<132>1 2022-03-01T12:00:00.500000Z worker-1 billing {} - [myemma@32473 \
+                 id=\"op\\\"1\\]\" producer=\"billing\" target=\"services::billing\" \
+                 file=\"src/billing.rs\" line=\"12\"] Retry invoice_id=5",
+                std::process::id()
+            ),
+            String::from_utf8_lossy(&buffer[..size])
+        );
+    }
+
+    #[test]
+    fn test_async_keeps_structured_data() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sink = LogSink::Async(
+            AsyncWriter::new(
+                LogSink::syslog_udp(server.local_addr().unwrap()).unwrap(),
+                AsyncWriterConfig::default(),
+            )
+            .unwrap(),
+        );
+        let entry = LogEntry::new(Level::Info, "services::billing", "Paid".to_owned());
+        sink.write_entry(&entry, "Paid", &FormatOptions::new("op1", "billing"));
+        sink.flush();
+
+        let mut buffer = [0; 1024];
+        let size = server.recv(&mut buffer).unwrap();
+        assert!(String::from_utf8_lossy(&buffer[..size]).ends_with(
+            "[myemma@32473 id=\"op1\" producer=\"billing\" target=\"services::billing\"] Paid"
+        ));
+    }
+
+    #[test]
+    fn test_truncate_header_fields_and_message() {
+        let server = UdpSocket::bind("[::1]:0").unwrap();
+        let sink = SyslogSink::udp(server.local_addr().unwrap())
+            .unwrap()
+            .with_hostname(&"h".repeat(300))
+            .with_max_message_size(400);
+        sink.send_line(Level::Info, &"x".repeat(500));
+
+        let mut buffer = [0; 1024];
+        let size = server.recv(&mut buffer).unwrap();
+        let message = String::from_utf8_lossy(&buffer[..size]);
+        assert_eq!(400, message.len());
+        assert!(message.contains(&format!(" {} - ", "h".repeat(255))));
+    }
+
+    #[test]
+    fn test_severity() {
+        assert_eq!(2, severity(Level::Error, true));
+        assert_eq!(3, severity(Level::Error, false));
+        assert_eq!(7, severity(Level::Trace, false));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{ColorMode, FormatOptions, LogEntry, LogFormat, LogSink, TextOptions, TraceContext};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+/// A record captured by the `TestLogger`.
+#[derive(Debug, Clone)]
+pub struct CapturedRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    /// Structured key/value fields.
+    pub fields: Map<String, Value>,
+    /// The `http_request` field of an access log entry.
+    pub http_request: Option<Value>,
+    /// The trace that was current when the record was logged.
+    pub trace_context: Option<TraceContext>,
+    /// The record in the Text format, without colors and backtrace.
+    pub formatted: String,
+}
+
+thread_local! {
+    static CAPTURED_RECORDS: RefCell<Vec<CapturedRecord>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Keeps all records of a thread in memory, so tests can check what was logged.
+///
+/// Only records logged on the thread of the test are captured, records of spawned threads
+/// (or the worker threads of an async runtime) are not. Tests can share a thread
+/// (for example with `--test-threads=1`), so call `init_test_logger` at the start of
+/// every test to remove the records of earlier tests.
+#[derive(Debug)]
+pub struct TestLogger {
+    options: FormatOptions,
+}
+
+impl Default for TestLogger {
+    fn default() -> Self {
+        let mut options = FormatOptions::new("test", "test");
+        options.text = TextOptions::default().with_color(ColorMode::Never);
+        Self {
+            options: options.with_colors_for(&LogSink::Stdout),
+        }
+    }
+}
+
+impl Log for TestLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let entry = LogEntry::from_record(record);
+        let captured = CapturedRecord {
+            level: entry.level,
+            target: entry.target.clone(),
+            message: entry.message.clone(),
+            fields: entry.fields.clone(),
+            http_request: entry.http_request.clone(),
+            trace_context: entry.trace_context.clone(),
+            formatted: LogFormat::Text.format_entry(&entry, &self.options),
+        };
+        CAPTURED_RECORDS.with(|records| records.borrow_mut().push(captured));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the `TestLogger` as global logger for all levels, can be called in every test.
+/// The records captured so far on this thread are removed.
+///
+/// Panics when another logger was installed.
+pub fn init_test_logger() {
+    static INSTALLED: OnceLock<()> = OnceLock::new();
+    INSTALLED.get_or_init(|| {
+        log::set_boxed_logger(Box::new(TestLogger::default()))
+            .expect("Another logger was installed before the test logger");
+        log::set_max_level(LevelFilter::Trace);
+    });
+    clear_captured_records();
+}
+
+/// All records captured on this thread.
+pub fn captured_records() -> Vec<CapturedRecord> {
+    CAPTURED_RECORDS.with(|records| records.borrow().clone())
+}
+
+pub fn clear_captured_records() {
+    CAPTURED_RECORDS.with(|records| records.borrow_mut().clear());
+}
+
+/// Whether a record of `level` was captured on this thread of which the formatted
+/// line (target, message and fields) contains `text`.
+pub fn is_logged(level: Level, text: &str) -> bool {
+    CAPTURED_RECORDS.with(|records| {
+        records
+            .borrow()
+            .iter()
+            .any(|record| record.level == level && record.formatted.contains(text))
+    })
+}
+
+/// Assert that a record of the level was logged on this thread, see `init_test_logger`.
+///
+/// `assert_logged!(Level::Error, contains = "Invoice failed");`
+#[macro_export]
+macro_rules! assert_logged {
+    ($level:expr, contains = $text:expr $(,)?) => {
+        if !$crate::is_logged($level, $text) {
+            panic!(
+                "No {} record containing {:?} was logged, captured records: {:#?}",
+                $level,
+                $text,
+                $crate::captured_records()
+                    .into_iter()
+                    .map(|record| record.formatted)
+                    .collect::<Vec<_>>()
+            );
+        }
+    };
+}
+
+/// Assert that no record of the level (containing the text) was logged on this thread.
+///
+/// `assert_not_logged!(Level::Error);`
+/// `assert_not_logged!(Level::Warn, contains = "retry");`
+#[macro_export]
+macro_rules! assert_not_logged {
+    ($level:expr $(,)?) => {
+        $crate::assert_not_logged!($level, contains = "")
+    };
+    ($level:expr, contains = $text:expr $(,)?) => {
+        if $crate::is_logged($level, $text) {
+            panic!(
+                "A {} record containing {:?} was logged, captured records: {:#?}",
+                $level,
+                $text,
+                $crate::captured_records()
+                    .into_iter()
+                    .map(|record| record.formatted)
+                    .collect::<Vec<_>>()
+            );
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_records() {
+        let logger = TestLogger::default();
+        logger.log(
+            &Record::builder()
+                .args(format_args!("Invoice failed"))
+                .level(Level::Error)
+                .target("services::billing")
+                .key_values(&[("invoice_id", 12)])
+                .build(),
+        );
+
+        let records = captured_records();
+        assert_eq!(1, records.len());
+        assert_eq!(
+            "ERROR:services::billing - Invoice failed invoice_id=12",
+            records[0].formatted
+        );
+        assert_eq!(12, records[0].fields["invoice_id"]);
+        assert_logged!(Level::Error, contains = "Invoice failed");
+        assert_logged!(Level::Error, contains = "invoice_id=12");
+        assert_not_logged!(Level::Warn);
+        assert_not_logged!(Level::Error, contains = "Payment");
+
+        clear_captured_records();
+        assert_not_logged!(Level::Error);
+    }
+
+    #[test]
+    #[should_panic(expected = "No ERROR record containing \"Payment\" was logged")]
+    fn test_assert_logged_fails() {
+        assert_logged!(Level::Error, contains = "Payment");
+    }
+}
@@ -0,0 +1,167 @@
+use std::cell::RefCell;
+
+thread_local! {
+    static THREAD_TRACE_CONTEXT: RefCell<Option<TraceContext>> = const { RefCell::new(None) };
+}
+
+#[cfg(feature = "tokio")]
+tokio::task_local! {
+    static TASK_TRACE_CONTEXT: TraceContext;
+}
+
+/// The trace of the request that is currently handled.
+/// All log entries written within the context are linked to the trace in the Json format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// 32 character hexadecimal trace id.
+    pub trace_id: String,
+    /// 16 character hexadecimal span id.
+    pub span_id: Option<String>,
+    /// The trace was sampled for storage in a trace backend.
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    /// Parse the W3C `traceparent` header: `00-<trace id>-<span id>-<flags>`.
+    pub fn from_traceparent(header: &str) -> Option<Self> {
+        let mut parts = header.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let flags = parts.next()?;
+        if version.len() != 2
+            || !is_hex(trace_id, 32)
+            || !is_hex(span_id, 16)
+            || !is_hex(flags, 2)
+            || trace_id.bytes().all(|byte| byte == b'0')
+        {
+            return None;
+        }
+        Some(Self {
+            trace_id: trace_id.to_lowercase(),
+            span_id: Some(span_id.to_lowercase()),
+            sampled: u8::from_str_radix(flags, 16).ok()? & 1 == 1,
+        })
+    }
+
+    /// Parse the Google Cloud `X-Cloud-Trace-Context` header: `<trace id>/<span id>;o=<options>`.
+    /// The span id in this header is decimal and is converted to hexadecimal.
+    pub fn from_cloud_trace_context(header: &str) -> Option<Self> {
+        let (trace_and_span, options) = match header.trim().split_once(';') {
+            Some((trace_and_span, options)) => (trace_and_span, Some(options)),
+            None => (header.trim(), None),
+        };
+        let (trace_id, span_id) = match trace_and_span.split_once('/') {
+            Some((trace_id, span_id)) => (trace_id, Some(span_id)),
+            None => (trace_and_span, None),
+        };
+        if !is_hex(trace_id, 32) {
+            return None;
+        }
+        Some(Self {
+            trace_id: trace_id.to_lowercase(),
+            span_id: span_id
+                .and_then(|span_id| span_id.parse::<u64>().ok())
+                .map(|span_id| format!("{:016x}", span_id)),
+            sampled: options == Some("o=1"),
+        })
+    }
+
+    /// Get the trace context of the current task (with the `tokio` feature) or thread.
+    pub fn current() -> Option<Self> {
+        #[cfg(feature = "tokio")]
+        if let Ok(trace_context) = TASK_TRACE_CONTEXT.try_with(Clone::clone) {
+            return Some(trace_context);
+        }
+        THREAD_TRACE_CONTEXT.with(|current| current.borrow().clone())
+    }
+
+    /// Make this the trace context of the current thread until the guard is dropped.
+    /// In async code use `scope` instead, because tasks can move between threads.
+    pub fn enter(self) -> TraceContextGuard {
+        let previous = THREAD_TRACE_CONTEXT.with(|current| current.replace(Some(self)));
+        TraceContextGuard { previous }
+    }
+
+    /// Make this the trace context of the `future`, also when it moves between threads.
+    #[cfg(feature = "tokio")]
+    pub async fn scope<F: std::future::Future>(self, future: F) -> F::Output {
+        TASK_TRACE_CONTEXT.scope(self, future).await
+    }
+}
+
+fn is_hex(value: &str, length: usize) -> bool {
+    value.len() == length && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// Restores the previous trace context of the thread when dropped.
+#[derive(Debug)]
+#[must_use = "The trace context is removed when the guard is dropped"]
+pub struct TraceContextGuard {
+    previous: Option<TraceContext>,
+}
+
+impl Drop for TraceContextGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        THREAD_TRACE_CONTEXT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        assert_eq!(
+            Some(TraceContext {
+                trace_id: "4bf92f3577b34da6a3ce929d0e0e4736".to_owned(),
+                span_id: Some("00f067aa0ba902b7".to_owned()),
+                sampled: true,
+            }),
+            TraceContext::from_traceparent(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+            )
+        );
+        assert_eq!(
+            None,
+            TraceContext::from_traceparent(
+                "00-00000000000000000000000000000000-00f067aa0ba902b7-01"
+            )
+        );
+        assert_eq!(None, TraceContext::from_traceparent("invalid"));
+    }
+
+    #[test]
+    fn test_parse_cloud_trace_context() {
+        assert_eq!(
+            Some(TraceContext {
+                trace_id: "105445aa7843bc8bf206b12000100000".to_owned(),
+                span_id: Some("000000000000004a".to_owned()),
+                sampled: true,
+            }),
+            TraceContext::from_cloud_trace_context("105445aa7843bc8bf206b12000100000/74;o=1")
+        );
+        assert_eq!(
+            Some(TraceContext {
+                trace_id: "105445aa7843bc8bf206b12000100000".to_owned(),
+                span_id: None,
+                sampled: false,
+            }),
+            TraceContext::from_cloud_trace_context("105445aa7843bc8bf206b12000100000")
+        );
+    }
+
+    #[test]
+    fn test_thread_trace_context() {
+        let trace_context =
+            TraceContext::from_cloud_trace_context("105445aa7843bc8bf206b12000100000").unwrap();
+        assert_eq!(None, TraceContext::current());
+        {
+            let _guard = trace_context.clone().enter();
+            assert_eq!(Some(trace_context), TraceContext::current());
+        }
+        assert_eq!(None, TraceContext::current());
+    }
+}
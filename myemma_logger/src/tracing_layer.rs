@@ -0,0 +1,174 @@
+use crate::log_fields::HTTP_REQUEST_FIELD;
+use crate::{LogEntry, LogFilter, Logger};
+use log::Level;
+use myemma_backtrace::BacktraceFilter;
+use serde_json::{Map, Value};
+use std::fmt::Debug;
+use tracing_core::field::{Field, Visit};
+use tracing_core::span::{Attributes, Id, Record};
+use tracing_core::{Event, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Name of the field that holds the message of a `tracing` event.
+const MESSAGE_FIELD: &str = "message";
+
+/// The fields of a span, stored in the extensions of the span.
+struct SpanFields(Map<String, Value>);
+
+/// Collects the fields of an event or span as JSON values.
+#[derive(Default)]
+struct FieldVisitor {
+    message: Option<String>,
+    fields: Map<String, Value>,
+}
+
+impl FieldVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        if field.name() == MESSAGE_FIELD {
+            self.message = Some(match value {
+                Value::String(text) => text,
+                other => other.to_string(),
+            });
+        } else {
+            self.fields.insert(field.name().to_owned(), value);
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.insert(field, Value::from(format!("{:?}", value)));
+    }
+}
+
+fn to_log_level(level: &tracing_core::Level) -> Level {
+    match *level {
+        tracing_core::Level::ERROR => Level::Error,
+        tracing_core::Level::WARN => Level::Warn,
+        tracing_core::Level::INFO => Level::Info,
+        tracing_core::Level::DEBUG => Level::Debug,
+        tracing_core::Level::TRACE => Level::Trace,
+    }
+}
+
+/// `tracing` events are formatted exactly like `log` records.
+///
+/// The fields of all spans around the event are added to the fields of the entry,
+/// fields of inner spans and of the event itself take precedence.
+impl<S, B, L> Layer<S> for Logger<B, L>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    B: BacktraceFilter + Sized + Send + Sync + 'static,
+    L: LogFilter + Sized + Send + Sync + Default + 'static,
+{
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        self.log_filter.filter(
+            &log::Metadata::builder()
+                .level(to_log_level(metadata.level()))
+                .target(metadata.target())
+                .build(),
+        )
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            match extensions.get_mut::<SpanFields>() {
+                Some(SpanFields(fields)) => fields.extend(visitor.fields),
+                None => extensions.insert(SpanFields(visitor.fields)),
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = to_log_level(metadata.level());
+        let mut fields = Map::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(SpanFields(span_fields)) = span.extensions().get::<SpanFields>() {
+                    fields.extend(span_fields.clone());
+                }
+            }
+        }
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        fields.extend(visitor.fields);
+
+        let mut entry = LogEntry::new(
+            level,
+            metadata.target(),
+            visitor.message.unwrap_or_default(),
+        );
+        entry.http_request = fields.remove(HTTP_REQUEST_FIELD);
+        entry.fields = fields;
+        entry.file = metadata.file().map(str::to_owned);
+        entry.line = metadata.line();
+        entry.module_path = metadata.module_path().map(str::to_owned);
+        self.log_entry(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::NoBacktraceFilter;
+    use crate::{LogFormat, LogSink, MemorySink};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_tracing_event_with_span_fields() {
+        let memory = MemorySink::default();
+        let logger = crate::Logger::<NoBacktraceFilter, crate::DirectiveFilter>::default()
+            .with_log_filter("info".parse().unwrap())
+            .with_format(LogFormat::Json)
+            .with_sink(LogSink::Memory(memory.clone()));
+        let subscriber = tracing_subscriber::registry().with(logger);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", user_id = 5, path = "/invoices");
+            let _guard = span.enter();
+            tracing::warn!(user_id = 6, retry = true, "Invoice {} failed", 12);
+            tracing::debug!("Not logged");
+        });
+
+        let lines = memory.lines();
+        assert_eq!(1, lines.len());
+        let entry: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!("warning", entry["severity"]);
+        assert_eq!("Invoice 12 failed", entry["message"]);
+        assert_eq!("/invoices", entry["path"]);
+        assert_eq!(6, entry["user_id"]);
+        assert_eq!(true, entry["retry"]);
+    }
+}
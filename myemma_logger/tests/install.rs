@@ -0,0 +1,52 @@
+//! Installs a global logger, so it runs in its own test binary.
+
+use myemma_backtrace::BacktraceFilter;
+use myemma_logger::log;
+use myemma_logger::{DirectiveFilter, LogEntry, LogFormat, LogSink, Logger, MemorySink, Redactor};
+
+struct NoBacktraceFilter;
+
+impl BacktraceFilter for NoBacktraceFilter {
+    fn filter(_symbol: &myemma_backtrace::backtrace::Symbol) -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_install() {
+    let memory = MemorySink::default();
+    let guard = Logger::<NoBacktraceFilter, DirectiveFilter>::default()
+        .with_log_filter("rocket=info,services=debug".parse().unwrap())
+        .with_format(LogFormat::Logfmt)
+        .with_rocket_bridge()
+        .with_redactor(Redactor::default())
+        .with_sink(LogSink::Memory(memory.clone()))
+        .install();
+    assert_eq!(log::LevelFilter::Debug, log::max_level());
+
+    log::warn!(target: "rocket::launch::_", "\u{1b}[1mport\u{1b}[0m: 8000");
+    log::debug!(target: "services::billing", "Invoice created");
+    log::debug!(target: "rocket::server", "Not logged");
+    let panic = LogEntry {
+        critical: true,
+        ..LogEntry::new(
+            log::Level::Error,
+            "panic",
+            "Connecting to postgres://app:hunter2@db/emma failed".to_owned(),
+        )
+    };
+    assert!(myemma_logger::log_panic(&panic));
+    drop(guard);
+
+    let lines = memory.lines();
+    assert_eq!(3, lines.len(), "{:?}", lines);
+    assert!(lines[0].contains(" level=info target=rocket::launch msg=\"port: 8000\""));
+    assert!(
+        lines[2].contains("postgres://app:[REDACTED]@db/emma"),
+        "{}",
+        lines[2]
+    );
+    assert!(Logger::<NoBacktraceFilter, DirectiveFilter>::default()
+        .try_install()
+        .is_err());
+}
@@ -0,0 +1,26 @@
+use myemma_logger::log::{self, Level};
+use myemma_logger::{assert_logged, assert_not_logged, init_test_logger};
+
+#[test]
+fn test_assert_logged() {
+    init_test_logger();
+    log::error!(target: "services::billing", "Invoice {} failed", 12);
+    log::debug!("Retrying");
+
+    assert_logged!(
+        Level::Error,
+        contains = "services::billing - Invoice 12 failed"
+    );
+    assert_logged!(Level::Debug, contains = "Retrying");
+    assert_not_logged!(Level::Warn);
+}
+
+#[test]
+fn test_records_per_thread() {
+    init_test_logger();
+    std::thread::spawn(|| log::error!("Other thread"))
+        .join()
+        .unwrap();
+
+    assert_not_logged!(Level::Error);
+}
@@ -7,17 +7,40 @@ pub extern crate myemma_logger;
 pub extern crate serde_json;
 
 /// Provide custom error with links for when application panics (unrecoverable error).
+///
+/// The panic is logged with the installed `Logger` (or `FanOutLogger`), so it gets the same
+/// redaction, format and sinks as all other entries.
+/// Without installed logger the panic is written to stdout, unless a `LogSink` is given as
+/// last argument, with the default `Redactor`.
 #[macro_export]
 macro_rules! setup_panic_hook {
     (
         $operation_identifier:literal, $producer_identifier:literal,
         $backtrace_filter:ty, $backtrace_count:literal $(,)*
+    ) => {
+        $crate::setup_panic_hook!(
+            $operation_identifier,
+            $producer_identifier,
+            $backtrace_filter,
+            $backtrace_count,
+            $crate::myemma_logger::LogSink::Stdout,
+        )
+    };
+    (
+        $operation_identifier:literal, $producer_identifier:literal,
+        $backtrace_filter:ty, $backtrace_count:literal, $sink:expr $(,)*
     ) => {
         // This code is inspired by the `human-panic` crate.
         // Only use custom panic when `RUST_BACKTRACE` is not set.
         match ::std::env::var("RUST_BACKTRACE") {
             Err(_) => {
-                std::panic::set_hook(Box::new(move |info: &std::panic::PanicInfo| {
+                let sink: $crate::myemma_logger::LogSink = $sink;
+                // Resolved once, the hook should not read the environment on every panic.
+                let format = $crate::myemma_logger::LogFormat::try_get_format()
+                    .unwrap_or($crate::myemma_logger::LogFormat::Text);
+                // Without installed logger the secrets are still removed.
+                let redactor = $crate::myemma_logger::Redactor::default();
+                std::panic::set_hook(Box::new(move |info: &std::panic::PanicHookInfo| {
                     let payload = info.payload();
                     let panic_message = if let Some(s) = payload.downcast_ref::<&str>() {
                         s.to_string()
@@ -42,46 +65,28 @@ macro_rules! setup_panic_hook {
                         // column = location.column();
                     }
 
-                    match $crate::myemma_logger::LogFormat::get_format() {
-                        $crate::myemma_logger::LogFormat::Text => {
-                            println!(
-                                "PANIC:{} - {}:{}{}",
-                                panic_message,
-                                file,
-                                line,
-                                <$backtrace_filter>::get_backtrace_info($backtrace_count),
-                            );
-                        }
-                        $crate::myemma_logger::LogFormat::Json => {
-                            let log_entry = $crate::google_cloud_logging::GoogleCloudStructLog {
-                                severity: Some($crate::google_cloud_logging::GCLogSeverity::Critical),
-                                // More info see: https://cloud.google.com/error-reporting/docs/formatting-error-messages#@type
-                                report_type: Some(
-                                    "type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent"
-                                    .to_owned()
-                                ),
-                                message: Some(
-                                    format!("{}{}", panic_message, <$backtrace_filter>::get_backtrace_info($backtrace_count))
-                                ),
-                                operation: Some($crate::google_cloud_logging::GCOperation {
-                                    id: Some($operation_identifier),
-                                    producer: Some($producer_identifier),
-                                    ..Default::default()
-                                }),
-                                source_location: Some($crate::google_cloud_logging::GCSourceLocation {
-                                    file: Some(file),
-                                    line: Some(line.to_string()),
-                                    function: None,
-                                }),
-                                time: Some($crate::chrono::Utc::now()),
-                                ..Default::default()
-                            };
-                            println!(
-                                "{}",
-                                $crate::serde_json::to_string(&log_entry).expect("Error during logging panic")
-                            );
-                        }
+                    let entry = $crate::myemma_logger::LogEntry {
+                        critical: true,
+                        backtrace: <$backtrace_filter>::get_backtrace_info($backtrace_count),
+                        file: Some(file.to_owned()),
+                        line: Some(line),
+                        ..$crate::myemma_logger::LogEntry::new(
+                            $crate::myemma_logger::log::Level::Error,
+                            "panic",
+                            panic_message,
+                        )
+                    };
+                    if $crate::myemma_logger::log_panic(&entry) {
+                        return;
                     }
+                    let mut entry = entry;
+                    redactor.redact_entry(&mut entry);
+                    let options = $crate::myemma_logger::FormatOptions::new(
+                        $operation_identifier,
+                        $producer_identifier,
+                    );
+                    sink.write_entry(&entry, &format.format_entry(&entry, &options), &options);
+                    sink.flush();
                 }));
             }
             Ok(_) => {}
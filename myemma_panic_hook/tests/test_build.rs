@@ -1,5 +1,5 @@
 use myemma_backtrace::{backtrace, BacktraceFilter};
-use myemma_panic_hook::setup_panic_hook;
+use myemma_panic_hook::{myemma_logger, setup_panic_hook};
 
 #[test]
 pub fn test_build() {
@@ -28,3 +28,14 @@ impl BacktraceFilter for CoreBacktraceFilter {
         false
     }
 }
+
+#[test]
+pub fn test_build_with_sink() {
+    setup_panic_hook!(
+        "MyEmma Backend",
+        "MyEmma Backend Core",
+        CoreBacktraceFilter,
+        8,
+        myemma_logger::LogSink::Stderr,
+    );
+}
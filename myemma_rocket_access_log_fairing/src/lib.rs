@@ -0,0 +1,261 @@
+#![forbid(unsafe_code)]
+#![deny(clippy::all)]
+
+use log::{kv, Level, Record};
+use myemma_logger::{TraceContext, HTTP_REQUEST_FIELD};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    Data, Request, Response,
+};
+use serde::Serialize;
+use std::time::Instant;
+
+/// This fairing logs one entry per request, with the method, url, status, latency,
+/// response size, remote ip and user agent.
+/// All requests are logged at `Info`, failing requests are already logged as errors
+/// by the handler or catcher, with a backtrace.
+///
+/// The request is added as the `http_request` field, so `myemma_logger::Logger` fills
+/// the `httpRequest` in the Json format and prints a compact line in the Text format.
+/// The entry is linked to the trace of the `traceparent` or `X-Cloud-Trace-Context` header,
+/// the same trace that `myemma_rocket_trace_fairing` uses for the logs of the handler.
+#[derive(Debug, Default)]
+pub struct AccessLogFairing {
+    excluded_paths: Vec<String>,
+}
+
+impl AccessLogFairing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Do not log requests to this path, for example health checks: `/health`.
+    pub fn with_excluded_path(mut self, path: &str) -> Self {
+        self.excluded_paths.push(path.to_owned());
+        self
+    }
+}
+
+/// The `httpRequest` of a Google Cloud log entry.
+/// https://cloud.google.com/logging/docs/reference/v2/rest/v2/LogEntry#HttpRequest
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HttpRequest {
+    request_method: String,
+    request_url: String,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remote_ip: Option<String>,
+    latency: String,
+}
+
+/// Get the trace of the request, shares the request-local value with the trace fairing.
+fn request_trace(req: &Request<'_>) -> Option<TraceContext> {
+    req.local_cache(|| {
+        let headers = req.headers();
+        headers
+            .get_one("traceparent")
+            .and_then(TraceContext::from_traceparent)
+            .or_else(|| {
+                headers
+                    .get_one("X-Cloud-Trace-Context")
+                    .and_then(TraceContext::from_cloud_trace_context)
+            })
+    })
+    .clone()
+}
+
+/// Time at which the request was received.
+struct RequestStart(Option<Instant>);
+
+#[rocket::async_trait]
+impl Fairing for AccessLogFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Access log",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        req.local_cache(|| RequestStart(Some(Instant::now())));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let path = req.uri().path();
+        if self
+            .excluded_paths
+            .iter()
+            .any(|excluded_path| path == excluded_path.as_str())
+        {
+            return;
+        }
+
+        let latency = req
+            .local_cache(|| RequestStart(None))
+            .0
+            .map(|start| start.elapsed().as_secs_f64())
+            .unwrap_or_default();
+        let status = res.status();
+        let http_request = HttpRequest {
+            request_method: req.method().as_str().to_owned(),
+            request_url: req.uri().to_string(),
+            status: status.code,
+            response_size: res.body().preset_size().map(|size| size.to_string()),
+            user_agent: req.headers().get_one("User-Agent").map(str::to_owned),
+            remote_ip: req.client_ip().map(|ip| ip.to_string()),
+            latency: format!("{:.6}s", latency),
+        };
+        if Level::Info > log::max_level() {
+            return;
+        }
+        // The handler has finished, so its trace scope has ended as well.
+        let _trace = request_trace(req).map(TraceContext::enter);
+        // The `log!` macro only takes literal keys, so the record is built here.
+        let key_values = [(HTTP_REQUEST_FIELD, kv::Value::from_serde(&http_request))];
+        log::logger().log(
+            &Record::builder()
+                .level(Level::Info)
+                .target(module_path!())
+                .module_path_static(Some(module_path!()))
+                .file_static(Some(file!()))
+                .line(Some(line!()))
+                .key_values(&key_values)
+                .args(format_args!(
+                    "{} {} {}",
+                    req.method(),
+                    req.uri(),
+                    status.code
+                ))
+                .build(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccessLogFairing;
+    use myemma_logger::log::Level;
+    use myemma_logger::{captured_records, init_test_logger};
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+    use rocket::response::{self, Responder, Response};
+    use rocket::Request;
+    use std::io::Cursor;
+
+    #[rocket::get("/health")]
+    fn health() -> &'static str {
+        "ok"
+    }
+
+    #[rocket::get("/users")]
+    fn users() -> &'static str {
+        "[]"
+    }
+
+    /// A body of which the size is not known up front.
+    struct Streamed;
+
+    impl<'r> Responder<'r, 'static> for Streamed {
+        fn respond_to(self, _req: &'r Request<'_>) -> response::Result<'static> {
+            Response::build()
+                .streamed_body(Cursor::new("streamed"))
+                .ok()
+        }
+    }
+
+    #[rocket::get("/stream")]
+    fn stream() -> Streamed {
+        Streamed
+    }
+
+    /// Install the test logger first, otherwise Rocket installs its own logger.
+    fn client() -> Client {
+        let rocket = rocket::build()
+            .attach(AccessLogFairing::new().with_excluded_path("/health"))
+            .mount("/", rocket::routes![health, users, stream]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    fn access_records() -> Vec<myemma_logger::CapturedRecord> {
+        captured_records()
+            .into_iter()
+            .filter(|record| record.target == "myemma_rocket_access_log_fairing")
+            .collect()
+    }
+
+    #[test]
+    fn test_excluded_path_is_not_logged() {
+        init_test_logger();
+        let client = client();
+        client.get("/health").dispatch();
+
+        assert!(access_records().is_empty());
+    }
+
+    #[test]
+    fn test_log_request() {
+        init_test_logger();
+        let client = client();
+        client
+            .get("/users")
+            .header(Header::new("User-Agent", "curl/8.0"))
+            .dispatch();
+
+        let records = access_records();
+        assert_eq!(1, records.len());
+        assert_eq!(Level::Info, records[0].level);
+        let http_request = records[0].http_request.as_ref().unwrap();
+        assert_eq!("GET", http_request["requestMethod"]);
+        assert_eq!("/users", http_request["requestUrl"]);
+        assert_eq!(200, http_request["status"]);
+        assert!(http_request["latency"].as_str().unwrap().ends_with('s'));
+        assert_eq!("2", http_request["responseSize"]);
+        assert_eq!("curl/8.0", http_request["userAgent"]);
+        assert!(
+            records[0].formatted.ends_with(" 2B"),
+            "{}",
+            records[0].formatted
+        );
+    }
+
+    #[test]
+    fn test_log_unknown_size() {
+        init_test_logger();
+        let client = client();
+        client.get("/stream").dispatch();
+
+        let records = access_records();
+        assert_eq!(1, records.len());
+        let http_request = records[0].http_request.as_ref().unwrap();
+        assert!(http_request.get("responseSize").is_none());
+        assert!(
+            records[0].formatted.ends_with(" -"),
+            "{}",
+            records[0].formatted
+        );
+    }
+
+    #[test]
+    fn test_log_with_trace() {
+        init_test_logger();
+        let client = client();
+        client
+            .get("/users")
+            .header(Header::new(
+                "traceparent",
+                "00-4bf92f3577b34da68f3ce929d0e0e473-00f067aa0ba902b7-01",
+            ))
+            .dispatch();
+
+        let records = access_records();
+        assert_eq!(1, records.len());
+        let trace_context = records[0].trace_context.as_ref().unwrap();
+        assert_eq!("4bf92f3577b34da68f3ce929d0e0e473", trace_context.trace_id);
+        assert_eq!(Some("00f067aa0ba902b7"), trace_context.span_id.as_deref());
+    }
+}
@@ -0,0 +1,165 @@
+#![forbid(unsafe_code)]
+#![deny(clippy::all)]
+
+use myemma_logger::ReloadHandle;
+use rocket::{
+    data::ToByteUnit,
+    http::{Method, Status},
+    route::{Handler, Outcome},
+    Data, Request,
+};
+
+/// Routes to read (`GET`) and change (`PUT`) the log directives at runtime.
+/// The body of the `PUT` request contains the new directives: `info,rocket=warn`.
+///
+/// Every request needs the header `Authorization: Bearer <token>`.
+/// Mount them on an admin path: `rocket.mount("/admin/log-level", log_level_routes(handle, token))`.
+pub fn log_level_routes(handle: ReloadHandle, token: &str) -> Vec<rocket::Route> {
+    let handler = LogLevelHandler {
+        handle,
+        token: token.to_owned(),
+    };
+    vec![
+        rocket::Route::new(Method::Get, "/", handler.clone()),
+        rocket::Route::new(Method::Put, "/", handler),
+    ]
+}
+
+#[derive(Clone)]
+struct LogLevelHandler {
+    handle: ReloadHandle,
+    token: String,
+}
+
+impl LogLevelHandler {
+    fn is_authorized(&self, req: &Request<'_>) -> bool {
+        req.headers()
+            .get_one("Authorization")
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .map(|token| constant_time_eq(token.as_bytes(), self.token.as_bytes()))
+            .unwrap_or_default()
+    }
+}
+
+/// Compare without returning early, so the token can not be guessed using the response time.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[rocket::async_trait]
+impl Handler for LogLevelHandler {
+    async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        if self.token.is_empty() || !self.is_authorized(req) {
+            log::warn!("Unauthorized request to change the log directives.");
+            return Outcome::error(Status::Unauthorized);
+        }
+        if req.method() == Method::Get {
+            return Outcome::from(req, self.handle.current().to_string());
+        }
+
+        let directives = match data.open(1.kibibytes()).into_string().await {
+            Ok(directives) if directives.is_complete() => directives.into_inner(),
+            Ok(_) => return Outcome::error(Status::PayloadTooLarge),
+            // The body is not valid UTF-8.
+            Err(err) if err.kind() == std::io::ErrorKind::InvalidData => {
+                return Outcome::error(Status::BadRequest)
+            }
+            Err(err) => {
+                log::error!("Could not read the new log directives: {}", err);
+                return Outcome::error(Status::InternalServerError);
+            }
+        };
+        match self.handle.reload(directives.trim()) {
+            Ok(()) => Outcome::from(req, self.handle.current().to_string()),
+            Err(err) => Outcome::from(req, (Status::BadRequest, err.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::log_level_routes;
+    use myemma_logger::{init_test_logger, ReloadHandle, ReloadableFilter};
+    use rocket::http::{Header, Status};
+    use rocket::local::blocking::Client;
+
+    /// Install the test logger first, otherwise Rocket installs its own logger
+    /// and changes the max level for every client.
+    fn client() -> (Client, ReloadHandle) {
+        init_test_logger();
+        let handle = ReloadableFilter::new("info".parse().unwrap()).handle();
+        let rocket = rocket::build().mount(
+            "/admin/log-level",
+            log_level_routes(handle.clone(), "secret"),
+        );
+        (Client::tracked(rocket).unwrap(), handle)
+    }
+
+    fn bearer(token: &str) -> Header<'static> {
+        Header::new("Authorization", format!("Bearer {}", token))
+    }
+
+    #[test]
+    fn test_unauthorized() {
+        let (client, handle) = client();
+        let response = client.get("/admin/log-level").dispatch();
+        assert_eq!(Status::Unauthorized, response.status());
+
+        let response = client
+            .put("/admin/log-level")
+            .header(bearer("wrong"))
+            .body("trace")
+            .dispatch();
+        assert_eq!(Status::Unauthorized, response.status());
+        assert_eq!("info", handle.current().to_string());
+    }
+
+    #[test]
+    fn test_get_directives() {
+        let (client, _handle) = client();
+        let response = client
+            .get("/admin/log-level")
+            .header(bearer("secret"))
+            .dispatch();
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!("info", response.into_string().unwrap());
+    }
+
+    #[test]
+    fn test_invalid_directives() {
+        let (client, handle) = client();
+        let response = client
+            .put("/admin/log-level")
+            .header(bearer("secret"))
+            .body("rocket=loud")
+            .dispatch();
+        assert_eq!(Status::BadRequest, response.status());
+        assert_eq!("info", handle.current().to_string());
+    }
+
+    #[test]
+    fn test_body_too_large() {
+        let (client, handle) = client();
+        let response = client
+            .put("/admin/log-level")
+            .header(bearer("secret"))
+            .body(format!("info,{}=debug", "a".repeat(2048)))
+            .dispatch();
+        assert_eq!(Status::PayloadTooLarge, response.status());
+        assert_eq!("info", handle.current().to_string());
+    }
+
+    #[test]
+    fn test_change_directives() {
+        let (client, handle) = client();
+        let response = client
+            .put("/admin/log-level")
+            .header(bearer("secret"))
+            .body("warn,services=debug\n")
+            .dispatch();
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!("warn,services=debug", response.into_string().unwrap());
+        assert_eq!("warn,services=debug", handle.current().to_string());
+        assert_eq!(log::LevelFilter::Debug, log::max_level());
+    }
+}
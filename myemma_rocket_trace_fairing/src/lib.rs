@@ -0,0 +1,185 @@
+#![forbid(unsafe_code)]
+#![deny(clippy::all)]
+
+use myemma_logger::TraceContext;
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    request::{self, FromRequest},
+    route::{Handler, Outcome},
+    Data, Request, Route,
+};
+
+/// This fairing reads the trace of a request from the `traceparent` or
+/// `X-Cloud-Trace-Context` header, so log entries can be grouped per request.
+///
+/// The trace is only used for the logs of a handler when the routes are wrapped with
+/// `traced_routes`, because a fairing can not wrap the execution of the handler:
+/// `rocket.attach(TraceFairing).mount("/", traced_routes(routes![...]))`.
+pub struct TraceFairing;
+
+#[rocket::async_trait]
+impl Fairing for TraceFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Trace",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        request_trace(req);
+    }
+}
+
+/// Get the trace of the request, it is only parsed once per request.
+fn request_trace(req: &Request<'_>) -> Option<TraceContext> {
+    req.local_cache(|| {
+        let headers = req.headers();
+        headers
+            .get_one("traceparent")
+            .and_then(TraceContext::from_traceparent)
+            .or_else(|| {
+                headers
+                    .get_one("X-Cloud-Trace-Context")
+                    .and_then(TraceContext::from_cloud_trace_context)
+            })
+    })
+    .clone()
+}
+
+/// Request guard with the trace of the current request.
+#[derive(Debug, Clone)]
+pub struct RequestTrace(pub Option<TraceContext>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestTrace {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(RequestTrace(request_trace(req)))
+    }
+}
+
+/// Run the handlers of the routes within the trace of the request,
+/// so all log entries of the handler are linked to the trace.
+pub fn traced_routes(routes: Vec<Route>) -> Vec<Route> {
+    routes
+        .into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(TracedHandler {
+                handler: route.handler,
+            });
+            route
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+struct TracedHandler {
+    handler: Box<dyn Handler>,
+}
+
+#[rocket::async_trait]
+impl Handler for TracedHandler {
+    async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        match request_trace(req) {
+            Some(trace_context) => trace_context.scope(self.handler.handle(req, data)).await,
+            None => self.handler.handle(req, data).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{traced_routes, RequestTrace, TraceFairing};
+    use myemma_logger::log;
+    use myemma_logger::{captured_records, init_test_logger};
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+
+    #[rocket::get("/trace")]
+    fn trace(trace: RequestTrace) -> String {
+        trace
+            .0
+            .map(|trace_context| trace_context.trace_id)
+            .unwrap_or_default()
+    }
+
+    #[rocket::get("/log")]
+    fn log_in_handler() {
+        log::info!("In handler");
+    }
+
+    /// Install the test logger first, otherwise Rocket installs its own logger.
+    fn client() -> Client {
+        init_test_logger();
+        let rocket = rocket::build()
+            .attach(TraceFairing)
+            .mount("/", rocket::routes![trace])
+            .mount("/traced", traced_routes(rocket::routes![log_in_handler]))
+            .mount("/plain", rocket::routes![log_in_handler]);
+        Client::tracked(rocket).unwrap()
+    }
+
+    fn get_trace(header: Option<Header<'static>>) -> String {
+        let client = client();
+        let mut request = client.get("/trace");
+        if let Some(header) = header {
+            request = request.header(header);
+        }
+        request.dispatch().into_string().unwrap()
+    }
+
+    #[test]
+    fn test_traceparent() {
+        assert_eq!(
+            "4bf92f3577b34da68f3ce929d0e0e473",
+            get_trace(Some(Header::new(
+                "traceparent",
+                "00-4bf92f3577b34da68f3ce929d0e0e473-00f067aa0ba902b7-01",
+            )))
+        );
+    }
+
+    #[test]
+    fn test_cloud_trace_context() {
+        assert_eq!(
+            "105445aa7843bc8bf206b12000100000",
+            get_trace(Some(Header::new(
+                "X-Cloud-Trace-Context",
+                "105445aa7843bc8bf206b12000100000/1;o=1",
+            )))
+        );
+    }
+
+    #[test]
+    fn test_malformed_header() {
+        assert_eq!(
+            "",
+            get_trace(Some(Header::new("traceparent", "00-not-a-trace-01")))
+        );
+        assert_eq!("", get_trace(None));
+    }
+
+    #[test]
+    fn test_traced_routes() {
+        let client = client();
+        let header = Header::new(
+            "traceparent",
+            "00-4bf92f3577b34da68f3ce929d0e0e473-00f067aa0ba902b7-01",
+        );
+        client.get("/traced/log").header(header.clone()).dispatch();
+        client.get("/plain/log").header(header).dispatch();
+
+        let records: Vec<_> = captured_records()
+            .into_iter()
+            .filter(|record| record.message == "In handler")
+            .collect();
+        assert_eq!(2, records.len());
+        assert_eq!(
+            "4bf92f3577b34da68f3ce929d0e0e473",
+            records[0].trace_context.as_ref().unwrap().trace_id
+        );
+        assert!(records[1].trace_context.is_none());
+    }
+}